@@ -0,0 +1,78 @@
+//! Syncs an account with a [`SyncSession`], against an in-process mock server answering with
+//! the test fixtures. Point `LwsRpcClient::new` at a real server to sync a real account.
+
+use std::{str::FromStr, time::Duration};
+
+use monero_lws::{
+    HeaderMap, HttpRequest, HttpResponse, HttpTransport, LwsRpcClientBuilder, StatusCode,
+    SyncEvent, SyncSession, SyncState, TransportFuture,
+};
+
+/// Answers every endpoint the session calls with its fixture.
+#[derive(Debug)]
+struct MockServer;
+
+impl HttpTransport for MockServer {
+    fn post(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let body = match request.url.rsplit('/').next() {
+                Some("login") => include_str!("../tests/fixtures/login.json"),
+                Some("import_wallet_request") => {
+                    include_str!("../tests/fixtures/import_wallet_request.json")
+                }
+                Some("get_address_info") => include_str!("../tests/fixtures/get_address_info.json"),
+                Some("get_address_txs") => include_str!("../tests/fixtures/get_address_txs.json"),
+                _ => "",
+            };
+            Ok(HttpResponse {
+                status: StatusCode::OK,
+                headers: HeaderMap::new(),
+                body: body.as_bytes().to_vec(),
+            })
+        })
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let address = monero::Address::from_str("888tNkZrPN6JsEgekjMnABU4TBzc2Dt29EPAvkRxbANsAnjyPbb3iQ1YBRk1UXcdRsiKc9dhwMVgN5S9cQUiyoogDavup3H")?;
+    let view_key = monero::PrivateKey::from_str(
+        "c2c5bf0e6c1a43f5e0c4a4b7ba7c1b8a3c8e7ab5a3e2f8a4c5d6e7f8091a2b0c",
+    )?;
+    let client = LwsRpcClientBuilder::new()
+        .transport(MockServer)
+        .build("http://lws.invalid")?;
+    let mut session = SyncSession::new(client.account(address, view_key)).import_from(2670000);
+
+    while session.state() != SyncState::Synced {
+        for event in session.step().await? {
+            match event {
+                SyncEvent::LoggedIn(login) => {
+                    println!("logged in, new account: {}", login.new_address)
+                }
+                SyncEvent::ImportRequested(import) => {
+                    println!("import requested: {}", import.status)
+                }
+                SyncEvent::Progress {
+                    scanned_block_height,
+                    blockchain_height,
+                } => println!("scanned {}/{}", scanned_block_height, blockchain_height),
+                SyncEvent::NewTransactions(txs) => {
+                    for tx in txs {
+                        println!("transaction {}: {:?}", tx.hash, tx.net_amount());
+                    }
+                }
+                SyncEvent::Confirmed(txs) => {
+                    for tx in txs {
+                        println!("transaction {} mined at {:?}", tx.hash, tx.height);
+                    }
+                }
+                SyncEvent::Synced => println!("synced"),
+            }
+        }
+        if session.state() == SyncState::Scanning {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+    Ok(())
+}
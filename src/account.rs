@@ -0,0 +1,202 @@
+// Rust Monero Light Wallet Server RPC Client
+// Written in 2021-2022 by
+//   Sebastian Kung <seb.kung@gmail.com>
+//   Monero Rust Contributors
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+use futures_util::future::try_join;
+
+use crate::{
+    AddressInfo, AddressTxs, BalanceSummary, ImportResponse, LoginResponse, LwsRpcClient,
+    Transaction, UnspentOuts,
+};
+
+/// Mixin used by [`Account::unspent_outs`], i.e. a ring size of 16.
+pub const DEFAULT_MIXIN: u32 = 15;
+/// Dust threshold used by [`Account::unspent_outs`], in piconero.
+pub const DEFAULT_DUST_THRESHOLD: u64 = 2_000_000_000;
+
+/// A light wallet account: a client bound to an address and its private view key, so that the
+/// credentials do not have to be passed to every call.
+#[derive(Clone, Debug)]
+pub struct Account {
+    client: LwsRpcClient,
+    address: monero::Address,
+    view_key: monero::PrivateKey,
+}
+
+impl Account {
+    pub fn new(
+        client: LwsRpcClient,
+        address: monero::Address,
+        view_key: monero::PrivateKey,
+    ) -> Self {
+        Self {
+            client,
+            address,
+            view_key,
+        }
+    }
+
+    /// Builds the account of the standard address derived from `view_pair` on `network`.
+    pub fn from_view_pair(
+        client: LwsRpcClient,
+        network: monero::Network,
+        view_pair: &monero::ViewPair,
+    ) -> Self {
+        let address = monero::Address::from_viewpair(network, view_pair);
+        Self::new(client, address, view_pair.view)
+    }
+
+    pub fn address(&self) -> monero::Address {
+        self.address
+    }
+
+    pub fn view_key(&self) -> monero::PrivateKey {
+        self.view_key
+    }
+
+    pub fn client(&self) -> &LwsRpcClient {
+        &self.client
+    }
+
+    pub async fn login(
+        &self,
+        create_account: bool,
+        generated_locally: bool,
+    ) -> anyhow::Result<LoginResponse> {
+        self.client
+            .login(
+                self.address,
+                self.view_key,
+                create_account,
+                generated_locally,
+            )
+            .await
+    }
+
+    /// Asks the server to scan the account from `from_height`, see
+    /// [`LwsRpcClient::import_request`].
+    pub async fn import_request(&self, from_height: Option<u64>) -> anyhow::Result<ImportResponse> {
+        self.client
+            .import_request(self.address, self.view_key, from_height)
+            .await
+    }
+
+    pub async fn info(&self) -> anyhow::Result<AddressInfo> {
+        self.client
+            .get_address_info(self.address, self.view_key)
+            .await
+    }
+
+    pub async fn txs(&self) -> anyhow::Result<AddressTxs> {
+        self.client
+            .get_address_txs(self.address, self.view_key)
+            .await
+    }
+
+    /// Fetches the info and the transactions of the account concurrently, keeping the
+    /// transactions mined at or above `since_height` and the ones in the mempool.
+    pub async fn refresh(&self, since_height: u64) -> anyhow::Result<Refresh> {
+        let (info, txs) = try_join(self.info(), self.txs()).await?;
+        let new_txs = txs
+            .transactions
+            .into_iter()
+            .filter(|tx| tx.height.is_none_or(|height| height >= since_height))
+            .collect();
+        Ok(Refresh { info, new_txs })
+    }
+
+    /// Fetches the outputs available to spend `amount`, using [`DEFAULT_MIXIN`] and
+    /// [`DEFAULT_DUST_THRESHOLD`]. Use [`LwsRpcClient::get_unspent_outs`] for other parameters.
+    pub async fn unspent_outs(&self, amount: monero::Amount) -> anyhow::Result<UnspentOuts> {
+        self.client
+            .get_unspent_outs(
+                self.address,
+                self.view_key,
+                amount,
+                DEFAULT_MIXIN,
+                true,
+                monero::Amount::from_pico(DEFAULT_DUST_THRESHOLD),
+            )
+            .await
+    }
+}
+
+/// Result of [`Account::refresh`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Refresh {
+    pub info: AddressInfo,
+    /// Transactions mined since the requested height, and mempool transactions.
+    pub new_txs: Vec<Transaction>,
+}
+
+/// Outcome of a batch of calls, e.g. [`LwsRpcClient::get_address_info_batch`]: the calls that
+/// succeeded and the ones that failed, each with its input, in the requested order.
+#[derive(Debug)]
+pub struct BatchResult<I, T> {
+    pub ok: Vec<(I, T)>,
+    pub errors: Vec<(I, anyhow::Error)>,
+}
+
+impl<I, T> BatchResult<I, T> {
+    pub(crate) fn new(results: Vec<(I, anyhow::Result<T>)>) -> Self {
+        let mut ok = Vec::new();
+        let mut errors = Vec::new();
+        for (input, result) in results {
+            match result {
+                Ok(value) => ok.push((input, value)),
+                Err(err) => errors.push((input, err)),
+            }
+        }
+        Self { ok, errors }
+    }
+
+    /// Fails with the first error if every call failed, keeps partial results otherwise. An
+    /// empty batch succeeds.
+    pub fn into_result(mut self) -> anyhow::Result<Self> {
+        if self.ok.is_empty() && !self.errors.is_empty() {
+            return Err(self.errors.swap_remove(0).1);
+        }
+        Ok(self)
+    }
+}
+
+/// Balances of several accounts, see [`LwsRpcClient::aggregate_balance`].
+#[derive(Debug)]
+pub struct AggregateBalance {
+    /// Balance of each account, or why it could not be fetched.
+    pub balances: BatchResult<monero::Address, BalanceSummary>,
+    /// Sum of the balances that could be fetched, saturating on overflow.
+    pub total: BalanceSummary,
+}
+
+impl AggregateBalance {
+    pub(crate) fn new(balances: BatchResult<monero::Address, BalanceSummary>) -> Self {
+        let mut total = BalanceSummary {
+            total_received: 0,
+            total_sent: 0,
+            locked: 0,
+            unlocked: 0,
+            net: 0,
+        };
+        for (_, balance) in &balances.ok {
+            total.total_received = total.total_received.saturating_add(balance.total_received);
+            total.total_sent = total.total_sent.saturating_add(balance.total_sent);
+            total.locked = total.locked.saturating_add(balance.locked);
+            total.unlocked = total.unlocked.saturating_add(balance.unlocked);
+            total.net += balance.net;
+        }
+        Self { balances, total }
+    }
+}
@@ -0,0 +1,158 @@
+// Rust Monero Light Wallet Server RPC Client
+// Written in 2021-2022 by
+//   Sebastian Kung <seb.kung@gmail.com>
+//   Monero Rust Contributors
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Variants of the transaction history models borrowing their strings from the response body,
+//! to parse large histories without allocating for every field. Strings containing escapes
+//! are still allocated. Parse them with `serde_json::from_slice` or `from_str`, and convert with
+//! `into_owned` when needed.
+
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    models::{mempool_height, number_or_boolean, string_or_number},
+    AddressTxs, HashString, KeyImage, PaymentIdField, PublicKey, SpendObject, Transaction, TxHash,
+};
+
+/// Borrowing variant of [`AddressTxs`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AddressTxsRef<'a> {
+    #[serde(borrow)]
+    pub total_received: Cow<'a, str>,
+    pub scanned_height: u64,
+    pub scanned_block_height: u64,
+    pub start_height: u64,
+    pub blockchain_height: u64,
+    // May not be present in version 0.3
+    #[serde(default, borrow)]
+    pub transactions: Vec<TransactionRef<'a>>,
+}
+
+impl AddressTxsRef<'_> {
+    pub fn into_owned(self) -> AddressTxs {
+        AddressTxs {
+            total_received: self.total_received.into_owned(),
+            scanned_height: self.scanned_height,
+            scanned_block_height: self.scanned_block_height,
+            start_height: self.start_height,
+            blockchain_height: self.blockchain_height,
+            transactions: self
+                .transactions
+                .into_iter()
+                .map(TransactionRef::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// Borrowing variant of [`Transaction`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(remote = "Self")]
+pub struct TransactionRef<'a> {
+    pub id: u64,
+    pub hash: HashString<TxHash>,
+    #[serde(borrow)]
+    pub timestamp: Cow<'a, str>,
+    #[serde(borrow)]
+    pub total_received: Cow<'a, str>,
+    #[serde(borrow)]
+    pub total_sent: Cow<'a, str>,
+    #[serde(deserialize_with = "string_or_number")]
+    pub unlock_time: u64,
+    #[serde(default)]
+    pub height: Option<u64>,
+    // May not be present in version 0.3
+    #[serde(default, borrow)]
+    pub spent_outputs: Vec<SpendObjectRef<'a>>,
+    pub payment_id: Option<PaymentIdField>,
+    #[serde(deserialize_with = "number_or_boolean")]
+    pub coinbase: bool,
+    #[serde(deserialize_with = "number_or_boolean")]
+    pub mempool: bool,
+    #[serde(deserialize_with = "string_or_number")]
+    pub mixin: u32,
+}
+
+impl Serialize for TransactionRef<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        TransactionRef::serialize(self, serializer)
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for TransactionRef<'a> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let mut tx = TransactionRef::deserialize(deserializer)?;
+        tx.height = mempool_height(tx.height, tx.mempool);
+        Ok(tx)
+    }
+}
+
+impl TransactionRef<'_> {
+    pub fn into_owned(self) -> Transaction {
+        Transaction {
+            id: self.id,
+            hash: self.hash,
+            timestamp: self.timestamp.into_owned(),
+            total_received: self.total_received.into_owned(),
+            total_sent: self.total_sent.into_owned(),
+            unlock_time: self.unlock_time,
+            height: self.height,
+            spent_outputs: self
+                .spent_outputs
+                .into_iter()
+                .map(SpendObjectRef::into_owned)
+                .collect(),
+            payment_id: self.payment_id,
+            coinbase: self.coinbase,
+            mempool: self.mempool,
+            mixin: self.mixin,
+        }
+    }
+}
+
+/// Borrowing variant of [`SpendObject`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpendObjectRef<'a> {
+    #[serde(borrow)]
+    pub amount: Cow<'a, str>,
+    #[serde(alias = "keyImage")]
+    pub key_image: HashString<KeyImage>,
+    #[serde(alias = "txPubKey")]
+    pub tx_pub_key: HashString<PublicKey>,
+    #[serde(alias = "outIndex")]
+    pub out_index: u16,
+    #[serde(deserialize_with = "string_or_number")]
+    pub mixin: u32,
+}
+
+impl SpendObjectRef<'_> {
+    pub fn into_owned(self) -> SpendObject {
+        SpendObject {
+            amount: self.amount.into_owned(),
+            key_image: self.key_image,
+            tx_pub_key: self.tx_pub_key,
+            out_index: self.out_index,
+            mixin: self.mixin,
+        }
+    }
+}
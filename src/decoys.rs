@@ -0,0 +1,159 @@
+// Rust Monero Light Wallet Server RPC Client
+// Written in 2021-2022 by
+//   Sebastian Kung <seb.kung@gmail.com>
+//   Monero Rust Contributors
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+#[cfg(feature = "ringct")]
+use monero::util::ringct::{CtKey, Key};
+use rand::{Rng, RngCore};
+#[cfg(feature = "ringct")]
+use std::convert::TryFrom;
+
+#[cfg(feature = "ringct")]
+use crate::LwsError;
+use crate::RandomOutput;
+
+/// Shape of the gamma distribution of the log of decoy ages, in seconds, used by the reference
+/// wallet.
+const GAMMA_SHAPE: f64 = 19.28;
+/// Scale of the gamma distribution of the log of decoy ages.
+const GAMMA_SCALE: f64 = 1.0 / 1.61;
+/// Approximate time between two outputs on mainnet, in seconds, converting decoy ages into a
+/// distance in global indices.
+const AVERAGE_OUTPUT_SECONDS: f64 = 1.6;
+/// Draws past the oldest output of the pool before picking uniformly instead.
+const MAX_GAMMA_DRAWS: usize = 100;
+
+/// Picks `count` distinct decoys from `pool`, e.g. the outputs of
+/// [`crate::RandomOutputs`], favoring recent ones like the reference wallet does.
+///
+/// The age of each decoy is drawn from the gamma distribution of the reference wallet and
+/// converted into a distance from the newest output of the pool, assuming a constant output
+/// rate; the output of the pool closest to that global index is picked. Ages older than the
+/// pool are redrawn, falling back to a uniform pick. Returns fewer decoys if the pool has fewer
+/// distinct outputs, sorted by global index.
+pub fn select_decoys_gamma(
+    pool: &[RandomOutput],
+    count: usize,
+    rng: &mut impl RngCore,
+) -> Vec<RandomOutput> {
+    let mut candidates: Vec<&RandomOutput> = pool.iter().collect();
+    candidates.sort_by_key(|output| output.global_index);
+    candidates.dedup_by_key(|output| output.global_index);
+    let (oldest, newest) = match (candidates.first(), candidates.last()) {
+        (Some(oldest), Some(newest)) => (oldest.global_index, newest.global_index),
+        _ => return Vec::new(),
+    };
+
+    let mut selected = Vec::with_capacity(count.min(candidates.len()));
+    while selected.len() < count && !candidates.is_empty() {
+        let target = (0..MAX_GAMMA_DRAWS).find_map(|_| {
+            let age = sample_gamma(rng, GAMMA_SHAPE, GAMMA_SCALE).exp();
+            let distance = (age / AVERAGE_OUTPUT_SECONDS) as u64;
+            newest
+                .checked_sub(distance)
+                .filter(|target| *target >= oldest)
+        });
+        let position = match target {
+            Some(target) => {
+                let after = candidates.partition_point(|output| output.global_index < target);
+                // the closest of the candidates around `target`
+                if after == candidates.len()
+                    || (after > 0
+                        && target - candidates[after - 1].global_index
+                            < candidates[after].global_index - target)
+                {
+                    after - 1
+                } else {
+                    after
+                }
+            }
+            None => rng.gen_range(0..candidates.len()),
+        };
+        selected.push(candidates.remove(position).clone());
+    }
+    selected.sort_by_key(|output| output.global_index);
+    selected
+}
+
+/// A decoy, or the real output, as a member of the ring of a RingCT input: the keys monero-rs
+/// transactions and CLSAG signatures are built from. The ring is referenced in the input by
+/// the global indices of its members, see [`crate::to_relative_offsets`].
+#[cfg(feature = "ringct")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RingMember {
+    pub global_index: u64,
+    /// One time public key of the output.
+    pub dest: monero::PublicKey,
+    /// Commitment to the amount of the output, in `mask` like in `RctSigBase::out_pk`.
+    pub commitment: CtKey,
+}
+
+#[cfg(feature = "ringct")]
+impl TryFrom<&RandomOutput> for RingMember {
+    type Error = LwsError;
+
+    /// Fails with [`LwsError::InvalidInput`] if the public key or the commitment is not a
+    /// point of the curve.
+    fn try_from(output: &RandomOutput) -> Result<Self, Self::Error> {
+        let point = |bytes: &[u8]| {
+            monero::PublicKey::from_slice(bytes).map_err(|err| LwsError::InvalidInput {
+                input: hex::encode(bytes),
+                source: Box::new(err),
+            })
+        };
+        let dest = point(output.public_key.0.as_bytes())?;
+        let commitment = point(output.rct.0.as_bytes())?;
+        Ok(RingMember {
+            global_index: output.global_index,
+            dest,
+            commitment: CtKey {
+                mask: Key::from(commitment.to_bytes()),
+            },
+        })
+    }
+}
+
+#[cfg(feature = "ringct")]
+impl TryFrom<RandomOutput> for RingMember {
+    type Error = LwsError;
+
+    fn try_from(output: RandomOutput) -> Result<Self, Self::Error> {
+        Self::try_from(&output)
+    }
+}
+
+/// Samples the gamma distribution with Marsaglia and Tsang's method, valid for `shape >= 1`.
+fn sample_gamma(rng: &mut impl RngCore, shape: f64, scale: f64) -> f64 {
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let x = sample_standard_normal(rng);
+        let v = (1.0 + c * x).powi(3);
+        if v <= 0.0 {
+            continue;
+        }
+        let u: f64 = rng.gen();
+        if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+            return d * v * scale;
+        }
+    }
+}
+
+/// Samples the standard normal distribution with the Box-Muller transform.
+fn sample_standard_normal(rng: &mut impl RngCore) -> f64 {
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
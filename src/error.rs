@@ -0,0 +1,188 @@
+// Rust Monero Light Wallet Server RPC Client
+// Written in 2021-2022 by
+//   Sebastian Kung <seb.kung@gmail.com>
+//   Monero Rust Contributors
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+use reqwest::StatusCode;
+use std::{fmt, time::Duration};
+
+/// Light wallet server specific errors. Client methods return them wrapped in an
+/// [`anyhow::Error`], use `downcast_ref::<LwsError>()` to match on them.
+#[derive(Debug)]
+pub enum LwsError {
+    /// The server answered with a failure status, optionally explaining why.
+    Server {
+        status: String,
+        reason: Option<String>,
+    },
+    /// The server does not know the account, e.g. because it never logged in.
+    AccountNotFound { reason: String },
+    /// The server answered with an unsuccessful HTTP status and no failure details.
+    HttpStatus { status: StatusCode, url: String },
+    /// The response body exceeded the configured limit, in bytes.
+    ResponseTooLarge { limit: usize },
+    /// The response nested arrays and objects deeper than the configured limit.
+    ResponseTooDeep { limit: usize },
+    /// The request could not be sent or its response could not be received.
+    Http { source: reqwest::Error },
+    /// The response is not valid JSON or does not have the expected shape. With the
+    /// `error-paths` feature, `path` points at the offending field, e.g. `outputs[0].amount`.
+    Deserialize {
+        path: Option<String>,
+        source: serde_json::Error,
+    },
+    /// The server returned an address on another network than the configured one.
+    NetworkMismatch {
+        expected: monero::Network,
+        found: monero::Network,
+    },
+    /// The spendable outputs do not cover the required amount, both in piconero.
+    InsufficientFunds { required: u64, available: u64 },
+    /// The transactions of a response do not add up to its total, in piconero, see
+    /// [`crate::AddressTxs::verify_totals`].
+    TotalsMismatch { reported: u64, computed: u64 },
+    /// An unspent output appears twice in a response, by global index or by transaction and
+    /// index, see [`crate::UnspentOuts::validate`].
+    DuplicateOutput {
+        global_index: u64,
+        tx_hash: String,
+        index: u16,
+    },
+    /// Fewer distinct decoys than required to build a ring.
+    NotEnoughDecoys { required: usize, available: usize },
+    /// An output appears twice in a ring.
+    DuplicateRingMember { global_index: u64 },
+    /// An operation did not complete within the given time.
+    Timeout { timeout: Duration },
+    /// An operation was cancelled through its cancellation token.
+    Cancelled,
+    /// A user supplied value could not be parsed.
+    InvalidInput {
+        input: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
+impl fmt::Display for LwsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LwsError::Server {
+                status,
+                reason: Some(reason),
+            } => write!(f, "server error ({}): {}", status, reason),
+            LwsError::Server {
+                status,
+                reason: None,
+            } => write!(f, "server error ({})", status),
+            LwsError::AccountNotFound { reason } => write!(f, "account not found: {}", reason),
+            LwsError::HttpStatus { status, url } => {
+                let kind = if status.is_server_error() {
+                    "server"
+                } else {
+                    "client"
+                };
+                write!(
+                    f,
+                    "HTTP status {} error ({}) for url ({})",
+                    kind, status, url
+                )
+            }
+            LwsError::ResponseTooLarge { limit } => {
+                write!(f, "response exceeds the limit of {} bytes", limit)
+            }
+            LwsError::ResponseTooDeep { limit } => {
+                write!(
+                    f,
+                    "response nests deeper than the limit of {} levels",
+                    limit
+                )
+            }
+            LwsError::Http { .. } => write!(f, "HTTP request failed"),
+            LwsError::Deserialize {
+                path: Some(path), ..
+            } => write!(f, "invalid response at `{}`", path),
+            LwsError::Deserialize { path: None, .. } => write!(f, "invalid response"),
+            LwsError::NetworkMismatch { expected, found } => write!(
+                f,
+                "server returned a {:?} address, expected {:?}",
+                found, expected
+            ),
+            LwsError::InsufficientFunds {
+                required,
+                available,
+            } => write!(
+                f,
+                "insufficient funds: {} piconero required, {} available",
+                required, available
+            ),
+            LwsError::TotalsMismatch { reported, computed } => write!(
+                f,
+                "transactions add up to {} piconero received, the response reports {}",
+                computed, reported
+            ),
+            LwsError::DuplicateOutput {
+                global_index,
+                tx_hash,
+                index,
+            } => write!(
+                f,
+                "output {} (output {} of transaction {}) appears twice",
+                global_index, index, tx_hash
+            ),
+            LwsError::NotEnoughDecoys {
+                required,
+                available,
+            } => write!(
+                f,
+                "not enough decoys: {} required, {} available",
+                required, available
+            ),
+            LwsError::DuplicateRingMember { global_index } => {
+                write!(f, "output {} appears twice in the ring", global_index)
+            }
+            LwsError::Timeout { timeout } => write!(f, "timed out after {:?}", timeout),
+            LwsError::Cancelled => write!(f, "cancelled"),
+            LwsError::InvalidInput { input, .. } => write!(f, "invalid input `{}`", input),
+        }
+    }
+}
+
+impl LwsError {
+    /// Error for a response with a failure `status`, [`LwsError::AccountNotFound`] when the
+    /// `reason` says the account is unknown.
+    pub(crate) fn server(status: String, reason: Option<String>) -> Self {
+        match reason {
+            Some(reason) if is_account_not_found(&reason) => LwsError::AccountNotFound { reason },
+            reason => LwsError::Server { status, reason },
+        }
+    }
+}
+
+/// Servers word it differently, e.g. "Address not found" or "account does not exist".
+fn is_account_not_found(reason: &str) -> bool {
+    let reason = reason.to_lowercase();
+    (reason.contains("address") || reason.contains("account"))
+        && (reason.contains("not found") || reason.contains("does not exist"))
+}
+
+impl std::error::Error for LwsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LwsError::Http { source } => Some(source),
+            LwsError::Deserialize { source, .. } => Some(source),
+            LwsError::InvalidInput { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
@@ -18,23 +18,46 @@
 
 // Coding conventions
 #![forbid(unsafe_code)]
+#![allow(unexpected_cfgs)]
 
 #[macro_use]
 mod util;
+mod account;
+#[cfg(feature = "zerocopy")]
+pub mod borrowed;
+mod decoys;
+mod error;
 mod models;
+pub mod parse;
+#[cfg(feature = "record")]
+pub mod record;
+mod session;
+#[cfg(feature = "metrics")]
+mod telemetry;
+mod transport;
 
-pub use self::{models::*, util::*};
+pub use self::{account::*, decoys::*, error::*, models::*, session::*, transport::*, util::*};
+// `jsonrpc_core` has an `Output` too, which would make the glob re-export ambiguous
+pub use self::models::Output;
 
+use futures_util::future::{join_all, select, Either};
 use jsonrpc_core::types::*;
 
-use serde::Deserialize;
+#[cfg(feature = "signing")]
+use reqwest::header::HeaderName;
+#[cfg(feature = "compression")]
+use reqwest::header::CONTENT_ENCODING;
+use reqwest::header::{HeaderValue, ACCEPT, CONTENT_TYPE, USER_AGENT};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
 use std::{
+    convert::TryFrom,
     fmt::Debug,
     iter::{empty, once},
     sync::Arc,
     time::Duration,
 };
+use tokio_util::sync::CancellationToken;
 
 enum RpcParams {
     Map(Box<dyn Iterator<Item = (String, Value)> + Send + 'static>),
@@ -59,26 +82,187 @@ impl From<RpcParams> for Params {
 
 #[derive(Clone, Debug)]
 struct RemoteCaller {
-    http_client: reqwest::Client,
+    transport: Arc<dyn HttpTransport>,
     addr: String,
+    max_response_bytes: usize,
+    max_json_depth: usize,
+    user_agent: HeaderValue,
+    network: Option<monero::Network>,
+    poll_retries: u32,
+    jsonrpc: bool,
+    #[cfg(feature = "compression")]
+    compress_requests: bool,
+    #[cfg(feature = "signing")]
+    signer: Option<(HmacKey, HeaderName)>,
 }
 
 impl RemoteCaller {
     async fn daemon_rpc_call<T>(&self, method: &'static str, params: RpcParams) -> anyhow::Result<T>
     where
-        T: for<'de> Deserialize<'de> + Send + 'static + Debug,
+        T: DeserializeOwned,
     {
-        let client = self.http_client.clone();
-        let uri = format!("{}/{}", &self.addr, method);
         let json_params: jsonrpc_core::types::params::Params = params.into();
-        let rsp = client.post(uri).json(&json_params).send().await?;
-        if rsp.status() != 200 {
-            rsp.error_for_status()?;
-            panic!("should never reach here");
-        }
-        let rsp = rsp.json::<T>().await?;
+        self.post(method, &json_params).await
+    }
+
+    async fn post<P, T>(&self, method: &str, params: &P) -> anyhow::Result<T>
+    where
+        P: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        let (rsp, _) = self.post_with_meta(method, params).await?;
         Ok(rsp)
     }
+
+    /// Posts `params` to `method` and parses the response into `T`. Metrics are recorded for
+    /// the whole call, a response that does not parse into `T` being a failed request.
+    async fn post_with_meta<P, T>(
+        &self,
+        method: &str,
+        params: &P,
+    ) -> anyhow::Result<(T, ResponseMeta)>
+    where
+        P: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let result = match self.send(method, params).await {
+            Ok((rsp, meta)) => parse::from_value(rsp)
+                .map(|rsp| (rsp, meta))
+                .map_err(Into::into),
+            Err(err) => Err(err),
+        };
+        #[cfg(feature = "metrics")]
+        telemetry::record(method, start.elapsed(), &result);
+        result
+    }
+
+    async fn send<P>(&self, method: &str, params: &P) -> anyhow::Result<(Value, ResponseMeta)>
+    where
+        P: Serialize + ?Sized,
+    {
+        let url = format!("{}/{}", &self.addr, method);
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+        headers.insert(USER_AGENT, self.user_agent.clone());
+        let body = serde_json::to_vec(params)?;
+        #[cfg(feature = "compression")]
+        let body = if self.compress_requests {
+            headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+            gzip(&body)?
+        } else {
+            body
+        };
+        #[cfg(feature = "signing")]
+        if let Some((key, header)) = &self.signer {
+            headers.insert(header.clone(), HeaderValue::from_str(&key.sign(&body))?);
+        }
+        let request = HttpRequest {
+            url: url.clone(),
+            headers,
+            body,
+        };
+        let rsp = self.transport.post(request).await?;
+        let limit = self.max_response_bytes;
+        if rsp.body.len() > limit {
+            return Err(LwsError::ResponseTooLarge { limit }.into());
+        }
+        let limit = self.max_json_depth;
+        if exceeds_depth(&rsp.body, limit) {
+            return Err(LwsError::ResponseTooDeep { limit }.into());
+        }
+        let body = rsp.body;
+        let status = rsp.status;
+        if !status.is_success() {
+            return Err(match server_failure(&body) {
+                Some(failure) => LwsError::server(status.to_string(), failure.reason),
+                None => LwsError::HttpStatus { status, url },
+            }
+            .into());
+        }
+        let meta = ResponseMeta::from_headers(status, &rsp.headers);
+        let value = if self.jsonrpc {
+            parse::parse_jsonrpc_value(&body)?
+        } else {
+            parse::parse_value(&body)?
+        };
+        Ok((value, meta))
+    }
+}
+
+#[cfg(feature = "compression")]
+fn gzip(body: &[u8]) -> std::io::Result<Vec<u8>> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(body)?;
+    encoder.finish()
+}
+
+/// Shared secret signing request bodies, see [`LwsRpcClientBuilder::hmac_key`]. Not shown by
+/// `Debug`.
+#[cfg(feature = "signing")]
+#[derive(Clone)]
+struct HmacKey(Vec<u8>);
+
+#[cfg(feature = "signing")]
+impl HmacKey {
+    /// Lowercase hex HMAC-SHA256 of `body`.
+    fn sign(&self, body: &[u8]) -> String {
+        use hmac::Mac;
+
+        let mut mac = hmac::Hmac::<sha2::Sha256>::new_from_slice(&self.0)
+            .expect("HMAC accepts keys of any length");
+        mac.update(body);
+        hex::encode(mac.finalize().into_bytes())
+    }
+}
+
+#[cfg(feature = "signing")]
+impl Debug for HmacKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("HmacKey(..)")
+    }
+}
+
+/// Whether the JSON in `body` nests arrays and objects deeper than `limit`. Only brackets are
+/// counted, the body is not validated.
+fn exceeds_depth(body: &[u8], limit: usize) -> bool {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for &byte in body {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                if depth > limit {
+                    return true;
+                }
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Extracts the failure details from the body of an unsuccessful response, if it has any.
+fn server_failure(body: &[u8]) -> Option<ServerFailure> {
+    serde_json::from_slice::<ServerFailure>(body)
+        .ok()
+        .filter(|failure| failure.reason.is_some())
 }
 
 #[derive(Clone, Debug)]
@@ -89,31 +273,323 @@ impl CallerWrapper {
     where
         T: for<'de> Deserialize<'de> + Send + 'static + Debug,
     {
-        let c = self.0.daemon_rpc_call(method, params).await?;
-        Ok(serde_json::from_value(c)?)
+        self.0.daemon_rpc_call(method, params).await
+    }
+}
+
+/// Default request timeout.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Default `User-Agent` header, `monero-lws-rs/<version>`.
+pub const DEFAULT_USER_AGENT: &str = concat!("monero-lws-rs/", env!("CARGO_PKG_VERSION"));
+/// Default limit on the size of a response body, 64 MiB.
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 64 * 1024 * 1024;
+/// Default limit on the nesting of arrays and objects in a response, 64 levels.
+pub const DEFAULT_MAX_JSON_DEPTH: usize = 64;
+/// Default number of times a poll is retried after a transport error, see
+/// [`LwsRpcClientBuilder::poll_retries`].
+pub const DEFAULT_POLL_RETRIES: u32 = 3;
+/// Default header carrying the signature of a request body, see
+/// [`LwsRpcClientBuilder::hmac_key`].
+#[cfg(feature = "signing")]
+pub const DEFAULT_HMAC_HEADER: &str = "X-Lws-Signature";
+
+#[derive(Clone, Debug)]
+struct LwsRpcClientConfig {
+    proxy_address: Option<String>,
+    timeout: Duration,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+    max_response_bytes: usize,
+    max_json_depth: usize,
+    user_agent: String,
+    network: Option<monero::Network>,
+    poll_retries: u32,
+    jsonrpc: bool,
+    path_prefix: Option<String>,
+    #[cfg(feature = "compression")]
+    compress_requests: bool,
+    #[cfg(feature = "signing")]
+    hmac_key: Option<HmacKey>,
+    #[cfg(feature = "signing")]
+    hmac_header: String,
+    #[cfg(feature = "record")]
+    record_to: Option<std::path::PathBuf>,
+    #[cfg(feature = "record")]
+    record_view_keys: bool,
+    transport: Option<Arc<dyn HttpTransport>>,
+}
+
+/// Builder for generating a configured [`LwsRpcClient`].
+#[derive(Clone, Debug)]
+pub struct LwsRpcClientBuilder {
+    config: LwsRpcClientConfig,
+}
+
+impl Default for LwsRpcClientBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LwsRpcClientBuilder {
+    /// Creates a new builder with the default configuration.
+    pub fn new() -> LwsRpcClientBuilder {
+        LwsRpcClientBuilder {
+            config: LwsRpcClientConfig {
+                proxy_address: None,
+                timeout: DEFAULT_TIMEOUT,
+                pool_max_idle_per_host: None,
+                pool_idle_timeout: None,
+                max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+                max_json_depth: DEFAULT_MAX_JSON_DEPTH,
+                user_agent: DEFAULT_USER_AGENT.to_string(),
+                network: None,
+                poll_retries: DEFAULT_POLL_RETRIES,
+                jsonrpc: false,
+                path_prefix: None,
+                #[cfg(feature = "compression")]
+                compress_requests: false,
+                #[cfg(feature = "signing")]
+                hmac_key: None,
+                #[cfg(feature = "signing")]
+                hmac_header: DEFAULT_HMAC_HEADER.to_string(),
+                #[cfg(feature = "record")]
+                record_to: None,
+                #[cfg(feature = "record")]
+                record_view_keys: false,
+                transport: None,
+            },
+        }
+    }
+
+    /// Adds a proxy to the generated client.
+    pub fn proxy_address(mut self, proxy: impl Into<String>) -> Self {
+        self.config.proxy_address = Some(proxy.into());
+        self
+    }
+
+    /// Configures the request timeout, [`DEFAULT_TIMEOUT`] if not set.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = timeout;
+        self
+    }
+
+    /// Limits the idle connections kept open to the server, unlimited if not set. Polling many
+    /// accounts concurrently opens as many connections, keep enough of them to reuse them for
+    /// the next round.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.config.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Closes connections idle for longer than `timeout`, 90 seconds if not set. Set it above
+    /// the polling interval so that connections are reused between polls.
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.config.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Limits how much of a response body is buffered, [`DEFAULT_MAX_RESPONSE_BYTES`] if not
+    /// set. Larger responses fail with [`LwsError::ResponseTooLarge`], which protects against
+    /// untrusted servers exhausting memory.
+    pub fn max_response_bytes(mut self, max_response_bytes: usize) -> Self {
+        self.config.max_response_bytes = max_response_bytes;
+        self
+    }
+
+    /// Limits how deeply arrays and objects may be nested in a response,
+    /// [`DEFAULT_MAX_JSON_DEPTH`] if not set. Deeper responses fail with
+    /// [`LwsError::ResponseTooDeep`] before being parsed.
+    pub fn max_json_depth(mut self, max_json_depth: usize) -> Self {
+        self.config.max_json_depth = max_json_depth;
+        self
+    }
+
+    /// Sets the `User-Agent` header sent with every request, [`DEFAULT_USER_AGENT`] if not set.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.config.user_agent = user_agent.into();
+        self
+    }
+
+    /// Checks that addresses returned by the server, e.g. [`ImportResponse::payment_address`],
+    /// are on `network`. Responses with an address on another network fail with
+    /// [`LwsError::NetworkMismatch`]. Not checked if not set.
+    pub fn network(mut self, network: monero::Network) -> Self {
+        self.config.network = Some(network);
+        self
+    }
+
+    /// Sets the [`network`](Self::network) to the one of `address`, e.g. an address a user
+    /// entered, see [`detect_network`].
+    pub fn network_of(self, address: &monero::Address) -> Self {
+        self.network(detect_network(address))
+    }
+
+    /// Retries each poll of [`LwsRpcClient::wait_until_scanned`] and
+    /// [`LwsRpcClient::submit_and_confirm`] up to `retries` times when the request fails in
+    /// transport, e.g. on a connection reset, [`DEFAULT_POLL_RETRIES`] if not set. The next
+    /// attempt opens a new connection after the poll interval. Failed responses, e.g. HTTP 4xx,
+    /// are not retried.
+    pub fn poll_retries(mut self, retries: u32) -> Self {
+        self.config.poll_retries = retries;
+        self
+    }
+
+    /// Expects responses wrapped in a JSON-RPC 2.0 envelope, e.g. by a gateway, and unwraps
+    /// their `result`. An `error` object fails with [`LwsError::Server`], with its `code` as the
+    /// status and its `message` as the reason. Off if not set: responses are the flat light
+    /// wallet objects.
+    pub fn jsonrpc(mut self, jsonrpc: bool) -> Self {
+        self.config.jsonrpc = jsonrpc;
+        self
+    }
+
+    /// Prepends `prefix` to the path of every endpoint, for servers behind a reverse proxy that
+    /// serves them under a sub path, e.g. `/lws` to post to `/lws/login`. Leading and trailing
+    /// slashes are ignored.
+    pub fn path_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.config.path_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Compresses request bodies with gzip, which only helps for large bodies such as
+    /// transactions. Off if not set: the server, or a proxy in front of it, must support
+    /// decompressing requests.
+    #[cfg(feature = "compression")]
+    pub fn compress_requests(mut self, compress_requests: bool) -> Self {
+        self.config.compress_requests = compress_requests;
+        self
+    }
+
+    /// Sends requests through `transport` instead of the default [`ReqwestTransport`], the
+    /// proxy, timeout and connection pool are then left to the transport.
+    pub fn transport(mut self, transport: impl HttpTransport + 'static) -> Self {
+        self.config.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Signs every request with `key`, a secret shared with the server or a proxy in front of
+    /// it. Requests are not signed if not set.
+    ///
+    /// The signature is the HMAC-SHA256 of the request body with `key`, as sent, i.e. after
+    /// compression if enabled, encoded as lowercase hex in the
+    /// [`DEFAULT_HMAC_HEADER`] header, or the one set by [`hmac_header`](Self::hmac_header).
+    #[cfg(feature = "signing")]
+    pub fn hmac_key(mut self, key: &[u8]) -> Self {
+        self.config.hmac_key = Some(HmacKey(key.to_vec()));
+        self
+    }
+
+    /// Sets the header carrying the signature, see [`hmac_key`](Self::hmac_key).
+    #[cfg(feature = "signing")]
+    pub fn hmac_header(mut self, name: impl Into<String>) -> Self {
+        self.config.hmac_header = name.into();
+        self
+    }
+
+    /// Appends every request and the response of the server to the file at `path`, to be
+    /// replayed with [`record::ReplayTransport`]. Requests are not recorded if not set.
+    #[cfg(feature = "record")]
+    pub fn record_to(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.config.record_to = Some(path.into());
+        self
+    }
+
+    /// Records view keys in clear instead of redacting them, see
+    /// [`record_to`](Self::record_to). Off if not set.
+    #[cfg(feature = "record")]
+    pub fn record_view_keys(mut self, record_view_keys: bool) -> Self {
+        self.config.record_view_keys = record_view_keys;
+        self
+    }
+
+    /// Build and return the fully configured client.
+    pub fn build(self, addr: impl Into<String>) -> anyhow::Result<LwsRpcClient> {
+        let config = self.config;
+        let transport = match config.transport {
+            Some(transport) => transport,
+            None => {
+                let mut http_client_builder = reqwest::ClientBuilder::new().timeout(config.timeout);
+                if let Some(max) = config.pool_max_idle_per_host {
+                    http_client_builder = http_client_builder.pool_max_idle_per_host(max);
+                }
+                if let Some(timeout) = config.pool_idle_timeout {
+                    http_client_builder = http_client_builder.pool_idle_timeout(timeout);
+                }
+                if let Some(proxy_address) = config.proxy_address {
+                    http_client_builder =
+                        http_client_builder.proxy(reqwest::Proxy::all(proxy_address)?);
+                }
+                Arc::new(ReqwestTransport::new(
+                    http_client_builder.build()?,
+                    config.max_response_bytes,
+                ))
+            }
+        };
+        #[cfg(feature = "record")]
+        let transport: Arc<dyn HttpTransport> = match config.record_to {
+            Some(path) => Arc::new(record::RecordingTransport::new(
+                transport,
+                &path,
+                !config.record_view_keys,
+            )?),
+            None => transport,
+        };
+        let mut addr = addr.into();
+        if let Some(prefix) = config.path_prefix {
+            let prefix = prefix.trim_matches('/');
+            if !prefix.is_empty() {
+                addr = format!("{}/{}", addr.trim_end_matches('/'), prefix);
+            }
+        }
+        Ok(LwsRpcClient {
+            inner: CallerWrapper(Arc::new(RemoteCaller {
+                transport,
+                addr,
+                max_response_bytes: config.max_response_bytes,
+                max_json_depth: config.max_json_depth,
+                user_agent: HeaderValue::from_str(&config.user_agent)?,
+                network: config.network,
+                poll_retries: config.poll_retries,
+                jsonrpc: config.jsonrpc,
+                #[cfg(feature = "compression")]
+                compress_requests: config.compress_requests,
+                #[cfg(feature = "signing")]
+                signer: match config.hmac_key {
+                    Some(key) => {
+                        Some((key, HeaderName::from_bytes(config.hmac_header.as_bytes())?))
+                    }
+                    None => None,
+                },
+            })),
+        })
     }
 }
 
 /// Base RPC client. It is useless on its own, please see the attached methods instead.
+///
+/// Cloning is cheap: clones share the same connection pool and configuration, so each task can
+/// hold its own handle.
 #[derive(Clone, Debug)]
 pub struct LwsRpcClient {
     inner: CallerWrapper,
 }
 
 impl LwsRpcClient {
+    /// Creates a client with the default configuration, panics if the proxy is invalid.
+    ///
+    /// **Use the [`LwsRpcClientBuilder`] for any other configuration.**
     pub fn new(addr: String, proxy: Option<String>) -> Self {
-        let mut client_builder = reqwest::ClientBuilder::new();
-        let timeout = Duration::from_secs(10);
-        client_builder = client_builder.timeout(timeout);
+        let mut builder = LwsRpcClientBuilder::new();
         if let Some(proxy_address) = proxy {
-            client_builder = client_builder.proxy(reqwest::Proxy::all(proxy_address).unwrap());
-        }
-        Self {
-            inner: CallerWrapper(Arc::new(RemoteCaller {
-                http_client: client_builder.build().unwrap(),
-                addr,
-            })),
+            builder = builder.proxy_address(proxy_address);
         }
+        builder.build(addr).unwrap()
+    }
+
+    /// Binds this client to an account, see [`Account`].
+    pub fn account(&self, address: monero::Address, view_key: monero::PrivateKey) -> Account {
+        Account::new(self.clone(), address, view_key)
     }
 
     pub async fn get_address_info(
@@ -142,6 +618,19 @@ impl LwsRpcClient {
             .await
     }
 
+    /// Like [`LwsRpcClient::get_address_txs`], but returns the response behind an [`Arc`] so it
+    /// can be handed to several consumers without cloning the transactions.
+    pub async fn get_address_txs_arc(
+        &self,
+        address: monero::Address,
+        view_key: monero::PrivateKey,
+    ) -> anyhow::Result<Arc<AddressTxs>> {
+        self.get_address_txs(address, view_key).await.map(Arc::new)
+    }
+
+    /// Fetches `count` random outputs for each of `amounts`, to use as decoys. A ring has one
+    /// real output, so `count` is usually the ring size minus one, see
+    /// [`LwsRpcClient::get_random_outs_for_ring_size`].
     pub async fn get_random_outs(
         &self,
         count: u32,
@@ -161,6 +650,179 @@ impl LwsRpcClient {
             .await
     }
 
+    /// Fetches the info of `accounts` concurrently. Accounts that fail, e.g. because the server
+    /// does not know them, are reported in [`BatchResult::errors`] instead of failing the whole
+    /// batch.
+    pub async fn get_address_info_batch(
+        &self,
+        accounts: &[Account],
+    ) -> BatchResult<monero::Address, AddressInfo> {
+        let results = join_all(accounts.iter().map(|account| async move {
+            let info = self
+                .get_address_info(account.address(), account.view_key())
+                .await;
+            (account.address(), info)
+        }))
+        .await;
+        BatchResult::new(results)
+    }
+
+    /// Fetches the balances of `accounts` concurrently and sums them. Accounts that fail are
+    /// reported like for [`LwsRpcClient::get_address_info_batch`].
+    pub async fn aggregate_balance(&self, accounts: &[Account]) -> AggregateBalance {
+        let results = join_all(accounts.iter().map(|account| async move {
+            let balance = self
+                .get_address_info(account.address(), account.view_key())
+                .await
+                .and_then(|info| Ok(BalanceSummary::try_from(&info)?));
+            (account.address(), balance)
+        }))
+        .await;
+        AggregateBalance::new(BatchResult::new(results))
+    }
+
+    /// Polls the info of `account` every `poll_interval` until the server has scanned it up to
+    /// the top of the chain, see [`AddressInfo::is_fully_scanned`], and returns the last info.
+    ///
+    /// Fails with [`LwsError::Timeout`] if that takes longer than `timeout`, or with the first
+    /// failed poll, once transport errors are retried, see
+    /// [`LwsRpcClientBuilder::poll_retries`]. Cancelling `cancel`, or dropping the future,
+    /// stops polling right away, even in the middle of a poll or of the wait between polls, and
+    /// fails with [`LwsError::Cancelled`].
+    pub async fn wait_until_scanned(
+        &self,
+        account: &Account,
+        poll_interval: Duration,
+        timeout: Duration,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<AddressInfo> {
+        let poll = async {
+            loop {
+                let info = self
+                    .retry_transport(poll_interval, || {
+                        self.get_address_info(account.address(), account.view_key())
+                    })
+                    .await?;
+                if info.is_fully_scanned() {
+                    return Ok(info);
+                }
+                tokio::time::sleep(poll_interval).await;
+            }
+        };
+        poll_until(poll, timeout, cancel).await
+    }
+
+    /// Fetches the decoys for rings of `ring_size` members, i.e. `ring_size - 1` random outputs
+    /// for each of `amounts`. Fails if `ring_size` is less than 2.
+    pub async fn get_random_outs_for_ring_size(
+        &self,
+        ring_size: u32,
+        amounts: Vec<monero::Amount>,
+    ) -> anyhow::Result<AmountOuts> {
+        anyhow::ensure!(ring_size >= 2, "ring size must be at least 2");
+        self.get_random_outs(ring_size - 1, amounts).await
+    }
+
+    /// Posts `body` to an arbitrary `endpoint`, e.g. one this crate does not model yet, and
+    /// returns the raw response. Failed responses are reported like for any other call.
+    #[cfg(feature = "raw")]
+    pub async fn call_raw(&self, endpoint: &str, body: Value) -> anyhow::Result<Value> {
+        self.inner
+            .0
+            .post(endpoint.trim_start_matches('/'), &body)
+            .await
+    }
+
+    /// Posts `body` to `endpoint` like [`LwsRpcClient::call_raw`], and also returns details of
+    /// the HTTP response, e.g. to log the server version. See [`ResponseMeta`] for the headers
+    /// captured; failed calls return the usual errors, without them.
+    pub async fn call_with_meta<T>(
+        &self,
+        endpoint: &str,
+        body: Value,
+    ) -> anyhow::Result<(T, ResponseMeta)>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        self.inner
+            .0
+            .post_with_meta(endpoint.trim_start_matches('/'), &body)
+            .await
+    }
+
+    /// Fetches the unspent outputs of `account`, selects the largest spendable ones until
+    /// `amount` and the fee are covered and fetches decoys for them, i.e. everything needed to
+    /// build a transaction with rings of `ring_size` members.
+    ///
+    /// Outputs are selected with [`UnspentOuts::select`], at the height of the chain reported
+    /// by the server and the fee of the response: locked outputs, see
+    /// [`models::Output::spendable_at`], are left out. The server only reports *possible* spends
+    /// in `spend_key_images`, as an output used as a decoy in another ring looks the same, so
+    /// outputs are only left out when one of those is in `spent_key_images`, the key images the
+    /// wallet computed for its own spends. One decoy more than needed is requested for each input
+    /// so that a decoy colliding with the real output can be dropped. Fails with
+    /// [`LwsError::InsufficientFunds`] if the outputs do not cover `amount` and the fee, and
+    /// with [`LwsError::DuplicateOutput`] if the server reports an output twice.
+    pub async fn prepare_spend(
+        &self,
+        account: &Account,
+        amount: monero::Amount,
+        ring_size: u32,
+        spent_key_images: &[KeyImage],
+    ) -> anyhow::Result<SpendContext> {
+        anyhow::ensure!(ring_size >= 2, "ring size must be at least 2");
+        let mut unspent = self
+            .get_unspent_outs(
+                account.address(),
+                account.view_key(),
+                amount,
+                ring_size - 1,
+                true,
+                monero::Amount::from_pico(DEFAULT_DUST_THRESHOLD),
+            )
+            .await?;
+        unspent.validate()?;
+        unspent.outputs.retain(|output| {
+            !output
+                .spend_key_images
+                .iter()
+                .any(|image| spent_key_images.contains(&image.0))
+        });
+        let info = self
+            .get_address_info(account.address(), account.view_key())
+            .await?;
+        // the top block is at `blockchain_height - 1`
+        let current_height = info.blockchain_height.saturating_sub(1);
+        let selection = unspent.select(
+            amount.as_pico(),
+            unspent.per_kb_fee,
+            unspent.fee_mask,
+            current_height,
+            ring_size as usize,
+        )?;
+        let outputs: Vec<_> = selection.inputs.into_iter().cloned().collect();
+
+        // RingCT outputs hide their amount, their decoys are requested for amount 0
+        let amounts = outputs
+            .iter()
+            .map(|output| {
+                if !output.is_rct() {
+                    output.amount.parse().map(monero::Amount::from_pico)
+                } else {
+                    Ok(monero::Amount::from_pico(0))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let decoys = self.get_random_outs(ring_size, amounts).await?;
+
+        Ok(SpendContext {
+            outputs,
+            decoys,
+            per_kb_fee: unspent.per_kb_fee,
+            fee_mask: unspent.fee_mask,
+        })
+    }
+
     pub async fn get_unspent_outs(
         &self,
         address: monero::Address,
@@ -185,6 +847,10 @@ impl LwsRpcClient {
             .await
     }
 
+    /// Asks the server to scan the account from `from_height`, or from the genesis block when
+    /// `None`. The light wallet API has no separate endpoint to set the scan height, this is
+    /// also how a wallet moves it forward past an irrelevant range. The server may require a
+    /// fee (see [`ImportResponse::payment_uri`]) or an approval first.
     pub async fn import_request(
         &self,
         address: monero::Address,
@@ -196,11 +862,102 @@ impl LwsRpcClient {
             .chain(once(("view_key", view_key.to_string().into())))
             .chain(from_height.map(|v| ("from_height", v.into())));
 
-        self.inner
+        let rsp: ImportResponse = self
+            .inner
             .request("import_wallet_request", RpcParams::map(params))
+            .await?;
+        if let Some(payment_address) = &rsp.payment_address {
+            self.check_network(payment_address)?;
+        }
+        Ok(rsp)
+    }
+
+    /// Broadcasts a signed transaction, serialized to its hex wire format.
+    pub async fn submit_raw_tx(
+        &self,
+        tx: &monero::Transaction,
+    ) -> anyhow::Result<SubmitRawTxResponse> {
+        let params = once(("tx", monero::consensus::encode::serialize_hex(tx).into()));
+        self.inner
+            .request("submit_raw_tx", RpcParams::map(params))
             .await
     }
 
+    /// Submits `tx` like [`LwsRpcClient::submit_raw_tx`], then polls the transactions of
+    /// `account` every `poll_interval` until `tx` shows up, in the mempool or mined. Returns the
+    /// hash of the transaction.
+    ///
+    /// Fails with [`LwsError::Timeout`] if `tx` is not seen within `timeout`, in which case it
+    /// may still be accepted later. Cancelling `cancel` before the submission fails with
+    /// [`LwsError::Cancelled`] without submitting `tx`; the submission itself is not cancelled,
+    /// but the polling after it stops right away, like for
+    /// [`LwsRpcClient::wait_until_scanned`].
+    pub async fn submit_and_confirm(
+        &self,
+        tx: &monero::Transaction,
+        account: &Account,
+        poll_interval: Duration,
+        timeout: Duration,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<monero::Hash> {
+        let hash = monero::cryptonote::hash::Hashable::hash(tx);
+        if cancel.is_cancelled() {
+            return Err(LwsError::Cancelled.into());
+        }
+        self.submit_raw_tx(tx).await?;
+        let poll = async {
+            loop {
+                let txs = self
+                    .retry_transport(poll_interval, || {
+                        self.get_address_txs(account.address(), account.view_key())
+                    })
+                    .await?;
+                if txs.transactions.iter().any(|tx| tx.hash.0 == hash.into()) {
+                    return Ok(hash);
+                }
+                tokio::time::sleep(poll_interval).await;
+            }
+        };
+        poll_until(poll, timeout, cancel).await
+    }
+
+    /// Runs `call` until it succeeds or fails with anything but [`LwsError::Http`], at most
+    /// [`LwsRpcClientBuilder::poll_retries`] more times, `interval` apart.
+    async fn retry_transport<T, F, Fut>(&self, interval: Duration, call: F) -> anyhow::Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = anyhow::Result<T>>,
+    {
+        let mut retries = 0;
+        loop {
+            match call().await {
+                Err(err)
+                    if retries < self.inner.0.poll_retries
+                        && matches!(
+                            err.downcast_ref::<LwsError>(),
+                            Some(LwsError::Http { .. })
+                        ) =>
+                {
+                    retries += 1;
+                    tokio::time::sleep(interval).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Fails if `address` is not on the configured network, see
+    /// [`LwsRpcClientBuilder::network`].
+    fn check_network(&self, address: &monero::Address) -> Result<(), LwsError> {
+        let found = detect_network(address);
+        match self.inner.0.network {
+            Some(expected) if expected != found => {
+                Err(LwsError::NetworkMismatch { expected, found })
+            }
+            _ => Ok(()),
+        }
+    }
+
     pub async fn login(
         &self,
         address: monero::Address,
@@ -216,3 +973,21 @@ impl LwsRpcClient {
         self.inner.request("login", RpcParams::map(params)).await
     }
 }
+
+/// Runs `poll` until it completes, fails with [`LwsError::Timeout`] after `timeout` and with
+/// [`LwsError::Cancelled`] as soon as `cancel` is cancelled.
+async fn poll_until<T>(
+    poll: impl std::future::Future<Output = anyhow::Result<T>>,
+    timeout: Duration,
+    cancel: &CancellationToken,
+) -> anyhow::Result<T> {
+    let poll = tokio::time::timeout(timeout, poll);
+    let cancelled = cancel.cancelled();
+    futures_util::pin_mut!(poll, cancelled);
+    match select(poll, cancelled).await {
+        Either::Left((result, _)) => {
+            result.unwrap_or_else(|_| Err(LwsError::Timeout { timeout }.into()))
+        }
+        Either::Right(_) => Err(LwsError::Cancelled.into()),
+    }
+}
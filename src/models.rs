@@ -13,20 +13,30 @@
 // The above copyright notice and this permission notice shall be included in all
 // copies or substantial portions of the Software.
 //
-#![allow(unexpected_cfgs)]
 
-use std::fmt;
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    fmt,
+    marker::PhantomData,
+    num::ParseIntError,
+    str::FromStr,
+};
 
-use crate::util::*;
+use crate::{util::*, LwsError};
 use monero::{cryptonote::hash::Hash as CryptoNoteHash, util::address::PaymentId};
+#[cfg(feature = "decimal")]
+use rust_decimal::Decimal;
 use serde::{
     de::{Error as DeserializerError, Visitor},
     Deserialize, Deserializer, Serialize,
 };
 
 macro_rules! hash_type {
-    ($name:ident, $len:expr) => {
+    ($(#[$attr:meta])* $name:ident, $len:expr) => {
         ::fixed_hash::construct_fixed_hash! {
+            $(#[$attr])*
             #[derive(::serde::Serialize, ::serde::Deserialize)]
             pub struct $name($len);
         }
@@ -35,28 +45,90 @@ macro_rules! hash_type {
 }
 
 hash_type!(BlockHash, 32);
+hash_type!(
+    /// Hash of a transaction.
+    TxHash,
+    32
+);
+hash_type!(
+    /// Key image of a spent output.
+    KeyImage,
+    32
+);
+hash_type!(
+    /// Compressed public key, e.g. of an output or of a transaction.
+    PublicKey,
+    32
+);
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+impl From<CryptoNoteHash> for TxHash {
+    fn from(hash: CryptoNoteHash) -> Self {
+        TxHash(hash.to_bytes())
+    }
+}
+
+impl From<TxHash> for CryptoNoteHash {
+    fn from(hash: TxHash) -> Self {
+        CryptoNoteHash(hash.0)
+    }
+}
+
+impl TryFrom<PublicKey> for monero::PublicKey {
+    type Error = monero::util::key::Error;
+
+    fn try_from(key: PublicKey) -> Result<Self, Self::Error> {
+        monero::PublicKey::from_slice(key.as_bytes())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Status {
     OK,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "status")]
 pub enum MoneroResult<T> {
     OK(T),
+    #[serde(alias = "error")]
+    Failed(ServerFailure),
 }
 
 impl<T> MoneroResult<T> {
-    pub fn into_inner(self) -> T {
+    pub fn into_inner(self) -> Result<T, LwsError> {
         match self {
-            MoneroResult::OK(v) => v,
+            MoneroResult::OK(v) => Ok(v),
+            MoneroResult::Failed(failure) => {
+                Err(LwsError::server("Failed".to_string(), failure.reason))
+            }
         }
     }
 }
 
-// Compatibility with version 0.1
-fn number_or_boolean<'de, D>(deserializer: D) -> Result<bool, D::Error>
+/// Body of a failed response. Servers disagree on the name of the field holding the
+/// human-readable message, all known spellings are accepted.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ServerFailure {
+    #[serde(
+        default,
+        alias = "error",
+        alias = "message",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub reason: Option<String>,
+}
+
+/// Statuses signaling a failed request in an otherwise successful HTTP response: the tags of
+/// [`MoneroResult::Failed`], keep them in sync.
+pub(crate) const FAILED_STATUSES: &[&str] = &["Failed", "error"];
+
+// Compatibility with version 0.1, and with servers quoting booleans. Used by
+// `Transaction::{coinbase, mempool}`, `Output::coinbase`,
+// `ImportResponse::{new_request, request_fulfilled}` and
+// `LoginResponse::{new_address, generated_locally}`, which only accept JSON booleans with the
+// `strict-bools` feature.
+#[cfg(not(feature = "strict-bools"))]
+pub(crate) fn number_or_boolean<'de, D>(deserializer: D) -> Result<bool, D::Error>
 where
     D: Deserializer<'de>,
 {
@@ -66,7 +138,7 @@ where
         type Value = bool;
 
         fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-            formatter.write_str("integer or boolean")
+            formatter.write_str("integer, boolean or string")
         }
 
         fn visit_bool<E: DeserializerError>(self, value: bool) -> Result<bool, E> {
@@ -86,12 +158,104 @@ where
             };
             Ok(boolean)
         }
+
+        fn visit_str<E: DeserializerError>(self, value: &str) -> Result<bool, E> {
+            match value {
+                "0" | "false" => Ok(false),
+                "1" | "true" => Ok(true),
+                _ => Err(E::invalid_value(serde::de::Unexpected::Str(value), &self)),
+            }
+        }
     }
 
     deserializer.deserialize_any(BoolVisitor)
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg(feature = "strict-bools")]
+pub(crate) fn number_or_boolean<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    bool::deserialize(deserializer)
+}
+
+// Some server versions quote integers
+pub(crate) fn string_or_number<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: TryFrom<u64> + FromStr,
+{
+    struct IntegerVisitor<T>(PhantomData<T>);
+
+    impl<'de, T> Visitor<'de> for IntegerVisitor<T>
+    where
+        T: TryFrom<u64> + FromStr,
+    {
+        type Value = T;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("integer or string containing an integer")
+        }
+
+        fn visit_u64<E: DeserializerError>(self, value: u64) -> Result<T, E> {
+            T::try_from(value)
+                .map_err(|_| E::invalid_value(serde::de::Unexpected::Unsigned(value), &self))
+        }
+
+        fn visit_str<E: DeserializerError>(self, value: &str) -> Result<T, E> {
+            value
+                .parse()
+                .map_err(|_| E::invalid_value(serde::de::Unexpected::Str(value), &self))
+        }
+    }
+
+    deserializer.deserialize_any(IntegerVisitor(PhantomData))
+}
+
+// Some server versions send a height of 0 instead of none for mempool transactions. Mined
+// transactions keep theirs, so that such a height is not mistaken for the mempool.
+pub(crate) fn mempool_height(height: Option<u64>, mempool: bool) -> Option<u64> {
+    height.filter(|height| !mempool || *height != 0)
+}
+
+// Some proxies re-serialize integers as floats
+fn integer_amount<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct AmountVisitor;
+
+    impl<'de> Visitor<'de> for AmountVisitor {
+        type Value = u64;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("unsigned integer amount")
+        }
+
+        fn visit_u64<E: DeserializerError>(self, value: u64) -> Result<u64, E> {
+            Ok(value)
+        }
+
+        fn visit_i64<E: DeserializerError>(self, value: i64) -> Result<u64, E> {
+            u64::try_from(value)
+                .map_err(|_| E::invalid_value(serde::de::Unexpected::Signed(value), &self))
+        }
+
+        fn visit_f64<E: DeserializerError>(self, value: f64) -> Result<u64, E> {
+            // `u64::MAX as f64` rounds up to 2^64, which does not fit
+            let integral = value.fract() == 0.0 && value >= 0.0 && value < u64::MAX as f64;
+            if cfg!(feature = "lenient-amounts") && integral {
+                Ok(value as u64)
+            } else {
+                Err(E::invalid_value(serde::de::Unexpected::Float(value), &self))
+            }
+        }
+    }
+
+    deserializer.deserialize_any(AmountVisitor)
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct AddressInfo {
     pub locked_funds: String,
     pub total_received: String,
@@ -99,28 +263,243 @@ pub struct AddressInfo {
     pub scanned_height: u64,
     pub scanned_block_height: u64,
     pub start_height: u64,
+    /// Height of the most recent transaction affecting the account, `0` when there is none. It
+    /// is at most `scanned_block_height`, which is how far the server has scanned regardless of
+    /// whether the blocks contained anything for this account.
     pub transaction_height: u64,
     pub blockchain_height: u64,
+    #[serde(default)]
     pub spent_outputs: Vec<SpendObject>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub rates: Option<Rates>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+impl AddressInfo {
+    /// Value of the balance (`total_received - total_sent`) in `currency`, `None` if the server
+    /// sent no rate for it or the amounts cannot be parsed.
+    ///
+    /// Computed with `f64`, which loses piconero precision above ~9000 XMR: fine for display,
+    /// not for accounting.
+    pub fn fiat_value(&self, currency: &str) -> Option<f64> {
+        let rate = self.rates.as_ref()?.get(currency)?;
+        let received: u64 = self.total_received.parse().ok()?;
+        let sent: u64 = self.total_sent.parse().ok()?;
+        let balance = received as f64 - sent as f64;
+        Some(balance / 1e12 * f64::from(rate))
+    }
+
+    /// Whether the server has scanned the account up to the top of the chain. The top block is
+    /// at `blockchain_height - 1`, so a one block lag is tolerated while a new block is being
+    /// scanned.
+    pub fn is_fully_scanned(&self) -> bool {
+        self.scanned_block_height.saturating_add(2) >= self.blockchain_height
+    }
+
+    /// Whether this response has scanned the account further than `other`, e.g. to pick
+    /// between servers queried for redundancy. When both scanned as far, the one knowing the
+    /// higher `blockchain_height` is more synced.
+    pub fn is_more_synced_than(&self, other: &AddressInfo) -> bool {
+        self.sync_position() > other.sync_position()
+    }
+
+    /// The most synced of `infos` (see [`AddressInfo::is_more_synced_than`]), the first one
+    /// on a tie. `None` if `infos` is empty.
+    pub fn most_synced(infos: &[AddressInfo]) -> Option<&AddressInfo> {
+        infos.iter().reduce(|best, info| {
+            if info.is_more_synced_than(best) {
+                info
+            } else {
+                best
+            }
+        })
+    }
+
+    fn sync_position(&self) -> (u64, u64) {
+        (self.scanned_block_height, self.blockchain_height)
+    }
+
+    /// Height of the most recent transaction affecting the account, if any.
+    pub fn latest_tx_height(&self) -> Option<u64> {
+        match self.transaction_height {
+            0 => None,
+            height => Some(height),
+        }
+    }
+
+    /// Compares with a `previous` snapshot of the same account and returns the first height
+    /// whose data should be discarded when a chain reorganization appears to have happened.
+    ///
+    /// The only signal used is the scanned block height going backwards: the server rewinds its
+    /// scan on a reorg, so everything above the new scanned height is no longer trustworthy. A
+    /// reorg that is rescanned entirely between two polls leaves the height unchanged and goes
+    /// unnoticed, and a server resetting the account (e.g. after a rescan request) is reported
+    /// as a reorg too.
+    pub fn detect_reorg(&self, previous: &AddressInfo) -> Option<u64> {
+        if self.scanned_block_height < previous.scanned_block_height {
+            Some(self.scanned_block_height + 1)
+        } else {
+            None
+        }
+    }
+}
+
+/// Balance of an account in piconero, derived from an [`AddressInfo`] for display.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceSummary {
+    pub total_received: u64,
+    pub total_sent: u64,
+    /// Funds the server reports as not spendable yet.
+    pub locked: u64,
+    /// `total_received - total_sent - locked`.
+    pub unlocked: u64,
+    /// `total_received - total_sent`, negative if the server reports more sent than received,
+    /// e.g. while its scan is incomplete.
+    pub net: i128,
+}
+
+#[cfg(feature = "decimal")]
+impl BalanceSummary {
+    pub fn total_received_xmr(&self) -> Decimal {
+        piconero_to_xmr(self.total_received)
+    }
+
+    pub fn total_sent_xmr(&self) -> Decimal {
+        piconero_to_xmr(self.total_sent)
+    }
+
+    pub fn locked_xmr(&self) -> Decimal {
+        piconero_to_xmr(self.locked)
+    }
+
+    pub fn unlocked_xmr(&self) -> Decimal {
+        piconero_to_xmr(self.unlocked)
+    }
+
+    pub fn net_xmr(&self) -> Decimal {
+        self.total_received_xmr() - self.total_sent_xmr()
+    }
+}
+
+impl TryFrom<&AddressInfo> for BalanceSummary {
+    type Error = LwsError;
+
+    /// Fails with [`LwsError::InvalidInput`] if an amount is not an integer.
+    fn try_from(info: &AddressInfo) -> Result<Self, Self::Error> {
+        let total_received = parse_amount(&info.total_received)?;
+        let total_sent = parse_amount(&info.total_sent)?;
+        let locked = parse_amount(&info.locked_funds)?;
+        Ok(BalanceSummary {
+            total_received,
+            total_sent,
+            locked,
+            unlocked: total_received
+                .saturating_sub(total_sent)
+                .saturating_sub(locked),
+            net: i128::from(total_received) - i128::from(total_sent),
+        })
+    }
+}
+
+fn parse_amount(amount: &str) -> Result<u64, LwsError> {
+    amount.parse().map_err(|err| LwsError::InvalidInput {
+        input: amount.to_string(),
+        source: Box::new(err),
+    })
+}
+
+/// Sync position of an account, the shape apps persist to resume scanning after a restart.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanState {
+    pub address: monero::Address,
+    /// Last block height scanned by the server.
+    pub last_height: u64,
+}
+
+impl ScanState {
+    pub fn from_info(address: monero::Address, info: &AddressInfo) -> Self {
+        Self {
+            address,
+            last_height: info.scanned_block_height,
+        }
+    }
+
+    pub fn from_txs(address: monero::Address, txs: &AddressTxs) -> Self {
+        Self {
+            address,
+            last_height: txs.scanned_block_height,
+        }
+    }
+}
+
+/// The most relevant fields of [`AddressInfo`], completed with [`AddressTxs`], the shape apps
+/// persist between sessions and compare across polls.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountSnapshot {
+    /// Height the totals were computed at.
+    pub scanned_block_height: u64,
+    pub blockchain_height: u64,
+    pub total_received: String,
+    pub total_sent: String,
+    pub locked_funds: String,
+    /// Height of the most recent mined transaction affecting the account, if any.
+    pub latest_tx_height: Option<u64>,
+}
+
+impl AccountSnapshot {
+    /// Completes the snapshot with `txs`: `blockchain_height` only moves forward and
+    /// `latest_tx_height` accounts for its mined transactions. The totals and
+    /// `scanned_block_height` are left as they are, as `txs` does not report all of them at
+    /// its own height: rebuild the snapshot from a fresh [`AddressInfo`] to update them.
+    pub fn merge(&mut self, txs: &AddressTxs) {
+        self.blockchain_height = self.blockchain_height.max(txs.blockchain_height);
+        let latest = txs.transactions.iter().filter_map(|tx| tx.height).max();
+        self.latest_tx_height = self.latest_tx_height.max(latest);
+    }
+}
+
+impl From<&AddressInfo> for AccountSnapshot {
+    fn from(info: &AddressInfo) -> Self {
+        Self {
+            scanned_block_height: info.scanned_block_height,
+            blockchain_height: info.blockchain_height,
+            total_received: info.total_received.clone(),
+            total_sent: info.total_sent.clone(),
+            locked_funds: info.locked_funds.clone(),
+            latest_tx_height: info.latest_tx_height(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[allow(non_snake_case)]
 pub struct Rates {
     pub AUD: Option<f32>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+impl Rates {
+    /// Rate of one XMR in `currency`, e.g. `"AUD"`, if the server sent it.
+    pub fn get(&self, currency: &str) -> Option<f32> {
+        match currency.to_ascii_uppercase().as_str() {
+            "AUD" => self.AUD,
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct SpendObject {
     pub amount: String,
-    pub key_image: HashString<CryptoNoteHash>,
-    pub tx_pub_key: HashString<CryptoNoteHash>,
+    #[serde(alias = "keyImage")]
+    pub key_image: HashString<KeyImage>,
+    #[serde(alias = "txPubKey")]
+    pub tx_pub_key: HashString<PublicKey>,
+    #[serde(alias = "outIndex")]
     pub out_index: u16,
+    #[serde(deserialize_with = "string_or_number")]
     pub mixin: u32,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct AddressTxs {
     pub total_received: String,
     pub scanned_height: u64,
@@ -132,72 +511,727 @@ pub struct AddressTxs {
     pub transactions: Vec<Transaction>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+impl AddressTxs {
+    /// Amount received by the transactions mined in blocks `from` to `to`, both included, in
+    /// piconero. Mempool transactions have no height and are not counted.
+    pub fn received_between(&self, from: u64, to: u64) -> Result<u64, LwsError> {
+        self.sum_between(from, to, |tx| &tx.total_received)
+    }
+
+    /// Amount sent by the transactions mined in blocks `from` to `to`, both included, in
+    /// piconero, change included. Mempool transactions are not counted.
+    pub fn sent_between(&self, from: u64, to: u64) -> Result<u64, LwsError> {
+        self.sum_between(from, to, |tx| &tx.total_sent)
+    }
+
+    fn sum_between<F>(&self, from: u64, to: u64, amount: F) -> Result<u64, LwsError>
+    where
+        F: Fn(&Transaction) -> &str,
+    {
+        self.transactions
+            .iter()
+            .filter(|tx| {
+                tx.height
+                    .is_some_and(|height| from <= height && height <= to)
+            })
+            .try_fold(0u64, |sum, tx| {
+                Ok(sum.saturating_add(parse_amount(amount(tx))?))
+            })
+    }
+
+    /// Checks that the amounts received by the confirmed transactions add up to
+    /// `total_received`, which fails with [`LwsError::TotalsMismatch`] when the list is
+    /// incomplete, e.g. truncated.
+    ///
+    /// Not checked when parsing: servers may count mempool transactions, or compute the total
+    /// at another height than the list, so a mismatch is not always an error.
+    pub fn verify_totals(&self) -> Result<(), LwsError> {
+        let reported = parse_amount(&self.total_received)?;
+        let computed = self
+            .transactions
+            .iter()
+            .filter(|tx| !tx.mempool)
+            .try_fold(0u64, |sum, tx| {
+                Ok::<_, LwsError>(sum.saturating_add(parse_amount(&tx.total_received)?))
+            })?;
+        if computed != reported {
+            return Err(LwsError::TotalsMismatch { reported, computed });
+        }
+        Ok(())
+    }
+
+    /// Transactions with duplicate hashes removed, keeping the confirmed instance over the
+    /// mempool one, in order of first appearance.
+    ///
+    /// Servers should not report a transaction twice, but one moving from the mempool into a
+    /// block while the response is built could be, which would count its amounts twice.
+    pub fn dedup_by_hash(&self) -> Vec<&Transaction> {
+        let mut deduped: Vec<&Transaction> = Vec::with_capacity(self.transactions.len());
+        let mut positions: HashMap<_, usize> = HashMap::new();
+        for tx in &self.transactions {
+            match positions.get(&tx.hash) {
+                Some(&i) => {
+                    if deduped[i].mempool && !tx.mempool {
+                        deduped[i] = tx;
+                    }
+                }
+                None => {
+                    positions.insert(&tx.hash, deduped.len());
+                    deduped.push(tx);
+                }
+            }
+        }
+        deduped
+    }
+
+    /// Merges a `newer` response for the same account into this one: the heights and
+    /// `total_received` are taken from `newer`, and its transactions replace the ones with the
+    /// same hash or are appended. A confirmed transaction is never replaced by a mempool
+    /// instance of itself.
+    ///
+    /// Transactions missing from `newer` are kept, so it can be a partial response; dropped
+    /// mempool transactions and reorganized blocks (see [`AddressInfo::detect_reorg`]) have to
+    /// be pruned separately.
+    pub fn merge(&mut self, newer: AddressTxs) {
+        self.total_received = newer.total_received;
+        self.scanned_height = newer.scanned_height;
+        self.scanned_block_height = newer.scanned_block_height;
+        self.start_height = newer.start_height;
+        self.blockchain_height = newer.blockchain_height;
+
+        let mut positions: HashMap<_, usize> = self
+            .transactions
+            .iter()
+            .enumerate()
+            .map(|(i, tx)| (tx.hash.clone(), i))
+            .collect();
+        for tx in newer.transactions {
+            match positions.get(&tx.hash) {
+                Some(&i) => {
+                    if self.transactions[i].mempool || !tx.mempool {
+                        self.transactions[i] = tx;
+                    }
+                }
+                None => {
+                    positions.insert(tx.hash.clone(), self.transactions.len());
+                    self.transactions.push(tx);
+                }
+            }
+        }
+    }
+
+    /// Transactions in chain order, each with the balance of the account after it, in
+    /// piconero. Mempool transactions come last: they are pending, their balance is the one
+    /// expected once they are mined.
+    ///
+    /// The balance starts from `0`, the history must start at the account creation for it to
+    /// be meaningful. Amounts that are not integers count as `0`.
+    pub fn with_running_balance(&self) -> Vec<(&Transaction, i128)> {
+        let mut txs: Vec<&Transaction> = self.transactions.iter().collect();
+        // stable, so transactions of the same block keep the server order
+        txs.sort_by_key(|tx| (tx.mempool, tx.height));
+        let mut balance = 0i128;
+        txs.into_iter()
+            .map(|tx| {
+                let received: u64 = tx.total_received.parse().unwrap_or(0);
+                let sent: u64 = tx.total_sent.parse().unwrap_or(0);
+                balance += i128::from(received) - i128::from(sent);
+                (tx, balance)
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "csv")]
+impl AddressTxs {
+    /// Writes the transactions as CSV with the columns `hash`, `height`, `timestamp`,
+    /// `total_received`, `total_sent`, `coinbase`, `mempool` and `payment_id`. The height of
+    /// mempool transactions and missing payment ids are left empty.
+    pub fn to_csv<W: std::io::Write>(&self, w: W) -> std::io::Result<()> {
+        let mut writer = csv::Writer::from_writer(w);
+        writer.write_record([
+            "hash",
+            "height",
+            "timestamp",
+            "total_received",
+            "total_sent",
+            "coinbase",
+            "mempool",
+            "payment_id",
+        ])?;
+        for tx in &self.transactions {
+            writer.write_record([
+                tx.hash.to_string(),
+                tx.height.map(|h| h.to_string()).unwrap_or_default(),
+                tx.timestamp.clone(),
+                tx.total_received.clone(),
+                tx.total_sent.clone(),
+                tx.coinbase.to_string(),
+                tx.mempool.to_string(),
+                tx.payment_id
+                    .as_ref()
+                    .map(|id| id.to_string())
+                    .unwrap_or_default(),
+            ])?;
+        }
+        writer.flush()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct Transaction {
     pub id: u64,
-    pub hash: HashString<CryptoNoteHash>,
+    pub hash: HashString<TxHash>,
     pub timestamp: String,
     pub total_received: String,
     pub total_sent: String,
+    #[serde(deserialize_with = "string_or_number")]
     pub unlock_time: u64,
+    /// Height of the block the transaction is mined in, `None` while in the mempool, including
+    /// when the server reports a height of 0 for it.
+    #[serde(default)]
     pub height: Option<u64>,
     // May not be present in version 0.3
     #[serde(default)]
     pub spent_outputs: Vec<SpendObject>,
-    pub payment_id: Option<HashString<PaymentId>>,
+    /// Short or long payment id, see [`PaymentIdField`].
+    pub payment_id: Option<PaymentIdField>,
     #[serde(deserialize_with = "number_or_boolean")]
     pub coinbase: bool,
     #[serde(deserialize_with = "number_or_boolean")]
     pub mempool: bool,
+    #[serde(deserialize_with = "string_or_number")]
     pub mixin: u32,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+impl Serialize for Transaction {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        Transaction::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Transaction {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut tx = Transaction::deserialize(deserializer)?;
+        tx.height = mempool_height(tx.height, tx.mempool);
+        Ok(tx)
+    }
+}
+
+/// Direction of a transaction relative to the account, see [`Transaction::direction`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TxDirection {
+    Incoming,
+    Outgoing,
+    /// The account both spent and received, e.g. a payment to itself or an outgoing payment
+    /// with change.
+    Internal,
+}
+
+impl Transaction {
+    /// Whether the transaction received funds, spent some, or both. Transactions with neither
+    /// are reported as incoming; amounts that are not integers count as `0`.
+    ///
+    /// Outgoing payments usually have change, so they are [`TxDirection::Internal`] too:
+    /// compare `total_sent` and `total_received` to tell them apart.
+    pub fn direction(&self) -> TxDirection {
+        let received = self.total_received.parse::<u64>().unwrap_or(0);
+        let sent = self.total_sent.parse::<u64>().unwrap_or(0);
+        match (received > 0, sent > 0) {
+            (true, true) => TxDirection::Internal,
+            (false, true) => TxDirection::Outgoing,
+            _ => TxDirection::Incoming,
+        }
+    }
+
+    /// Net effect of the transaction on the balance, `total_received - total_sent` in
+    /// piconero: positive if it brought funds in, negative if it took funds out, e.g. an
+    /// outgoing payment net of its change.
+    pub fn net_amount(&self) -> Result<i128, ParseIntError> {
+        let received = self.total_received.parse::<u64>()?;
+        let sent = self.total_sent.parse::<u64>()?;
+        Ok(i128::from(received) - i128::from(sent))
+    }
+
+    /// `total_received` in XMR, see [`piconero_to_xmr`].
+    #[cfg(feature = "decimal")]
+    pub fn total_received_xmr(&self) -> Result<Decimal, LwsError> {
+        parse_amount(&self.total_received).map(piconero_to_xmr)
+    }
+
+    /// `total_sent` in XMR, see [`piconero_to_xmr`].
+    #[cfg(feature = "decimal")]
+    pub fn total_sent_xmr(&self) -> Result<Decimal, LwsError> {
+        parse_amount(&self.total_sent).map(piconero_to_xmr)
+    }
+
+    /// Number of confirmations at `current_height`, the height of the latest block: `1` once
+    /// mined in it. `None` for mempool transactions, and `Some(0)` if the transaction is above
+    /// `current_height`, e.g. while a reorg is being processed.
+    pub fn confirmations(&self, current_height: u64) -> Option<u64> {
+        self.height.map(|height| {
+            current_height
+                .checked_sub(height)
+                .map_or(0, |depth| depth + 1)
+        })
+    }
+
+    /// Whether the transaction has at least `min` confirmations at `current_height`.
+    pub fn is_confirmed_with(&self, min: u64, current_height: u64) -> bool {
+        self.confirmations(current_height)
+            .is_some_and(|confirmations| confirmations >= min)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct AmountOuts {
-    pub amount_outs: Vec<RandomOutput>,
+    #[serde(default)]
+    pub amount_outs: Vec<RandomOutputs>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+impl AmountOuts {
+    /// Decoys indexed by amount in piconero, `0` for RingCT outputs. Entries whose amount is
+    /// not an integer are left out.
+    pub fn by_amount(&self) -> HashMap<u64, &RandomOutputs> {
+        self.amount_outs
+            .iter()
+            .filter_map(|outputs| Some((outputs.amount.parse().ok()?, outputs)))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct RandomOutputs {
     pub amount: String,
+    #[serde(default)]
     pub outputs: Vec<RandomOutput>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+impl RandomOutputs {
+    /// Decoys other than the one at global index `exclude`, typically the real output.
+    pub fn without_index(&self, exclude: u64) -> Vec<&RandomOutput> {
+        self.outputs
+            .iter()
+            .filter(|output| output.global_index != exclude)
+            .collect()
+    }
+
+    /// Assembles the ring of `real`: the first `ring_size - 1` decoys with distinct global
+    /// indices other than the one of `real`, and `real`, sorted by global index as the daemon
+    /// expects. Fails with [`LwsError::NotEnoughDecoys`] if there are not enough of them, and
+    /// with [`LwsError::InvalidInput`] if `ring_size` is less than 2, a ring without decoys
+    /// revealing the real output.
+    pub fn build_ring(
+        &self,
+        real: RandomOutput,
+        ring_size: usize,
+    ) -> Result<Vec<RandomOutput>, LwsError> {
+        if ring_size < 2 {
+            return Err(LwsError::InvalidInput {
+                input: ring_size.to_string(),
+                source: "ring size must be at least 2".into(),
+            });
+        }
+        let required = ring_size - 1;
+        let mut seen = HashSet::with_capacity(ring_size);
+        seen.insert(real.global_index);
+        let mut ring: Vec<RandomOutput> = self
+            .outputs
+            .iter()
+            .filter(|output| seen.insert(output.global_index))
+            .take(required)
+            .cloned()
+            .collect();
+        if ring.len() < required {
+            return Err(LwsError::NotEnoughDecoys {
+                required,
+                available: ring.len(),
+            });
+        }
+        ring.push(real);
+        ring.sort_by_key(|output| output.global_index);
+        Ok(ring)
+    }
+}
+
+/// Checks that the real output at `real_index` and the `decoys` all have distinct global
+/// indices, a ring with a repeated member is rejected by the daemon.
+pub fn validate_ring(real_index: u64, decoys: &[&RandomOutput]) -> Result<(), LwsError> {
+    let mut seen = HashSet::with_capacity(decoys.len() + 1);
+    seen.insert(real_index);
+    for decoy in decoys {
+        if !seen.insert(decoy.global_index) {
+            return Err(LwsError::DuplicateRingMember {
+                global_index: decoy.global_index,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct RandomOutput {
     pub global_index: u64,
-    pub public_key: HashString<CryptoNoteHash>,
+    pub public_key: HashString<PublicKey>,
     pub rct: HashString<CryptoNoteHash>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct UnspentOuts {
+    #[serde(deserialize_with = "integer_amount", alias = "perKbFee")]
     pub per_kb_fee: u64,
+    #[serde(deserialize_with = "integer_amount", alias = "feeMask")]
     pub fee_mask: u64,
     pub amount: String,
+    #[serde(default)]
     pub outputs: Vec<Output>,
+    /// Fee per byte of each priority tier, from the lowest to the highest, for servers
+    /// reporting them. Empty otherwise.
+    #[serde(rename = "fees", default, skip_serializing_if = "Vec::is_empty")]
+    pub fee_tiers: Vec<u64>,
+}
+
+/// Priority of a transaction, which sets its fee, see [`UnspentOuts::fee_for_priority`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FeePriority {
+    Low,
+    #[default]
+    Normal,
+    Elevated,
+    Priority,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+impl UnspentOuts {
+    /// Fee for `priority`, from [`UnspentOuts::fee_tiers`]. Falls back to the highest tier
+    /// reported when the server reports fewer tiers, and to `per_kb_fee` when it reports none.
+    pub fn fee_for_priority(&self, priority: FeePriority) -> u64 {
+        let tier = match priority {
+            FeePriority::Low => 0,
+            FeePriority::Normal => 1,
+            FeePriority::Elevated => 2,
+            FeePriority::Priority => 3,
+        };
+        self.fee_tiers
+            .get(tier)
+            .or_else(|| self.fee_tiers.last())
+            .copied()
+            .unwrap_or(self.per_kb_fee)
+    }
+
+    /// Checks that no output appears twice, by global index or by transaction and index, which
+    /// fails with [`LwsError::DuplicateOutput`]. Spending such a response would use the same
+    /// output twice.
+    pub fn validate(&self) -> Result<(), LwsError> {
+        let mut global_indices = HashSet::new();
+        let mut positions = HashSet::new();
+        for output in &self.outputs {
+            if !global_indices.insert(output.global_index)
+                || !positions.insert((output.tx_hash.0, output.index))
+            {
+                return Err(LwsError::DuplicateOutput {
+                    global_index: output.global_index,
+                    tx_hash: output.tx_hash.to_string(),
+                    index: output.index,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Outputs whose global index is not in `exclude`, e.g. outputs already reserved by a
+    /// transaction in flight. Servers have no way to exclude outputs, so this filters the
+    /// response instead.
+    pub fn excluding(&self, exclude: &HashSet<u64>) -> Vec<&Output> {
+        self.outputs
+            .iter()
+            .filter(|output| !exclude.contains(&output.global_index))
+            .collect()
+    }
+
+    /// Outputs from the largest to the smallest amount, e.g. to spend as few outputs as
+    /// possible. Amounts that are not integers count as `0`; ties are ordered by global index.
+    pub fn sorted_by_amount_desc(&self) -> Vec<&Output> {
+        let mut outputs: Vec<&Output> = self.outputs.iter().collect();
+        outputs.sort_by_key(|output| {
+            (
+                Reverse(output.amount.parse::<u64>().unwrap_or(0)),
+                output.global_index,
+            )
+        });
+        outputs
+    }
+
+    /// Outputs from the oldest to the newest at `current_height`, e.g. to consolidate old
+    /// outputs. Outputs above `current_height` count as new; ties are ordered by global index.
+    pub fn sorted_by_age(&self, current_height: u64) -> Vec<&Output> {
+        let mut outputs: Vec<&Output> = self.outputs.iter().collect();
+        outputs.sort_by_key(|output| {
+            (
+                Reverse(current_height.saturating_sub(output.height)),
+                output.global_index,
+            )
+        });
+        outputs
+    }
+
+    /// Fee of a transaction spending `n_inputs` to `n_outputs` with rings of `ring_size`
+    /// members, at the fee of the response, see [`estimate_tx_weight`].
+    pub fn estimate_fee(&self, n_inputs: usize, n_outputs: usize, ring_size: usize) -> u64 {
+        fee_for_weight(
+            estimate_tx_weight(n_inputs, n_outputs, ring_size),
+            self.per_kb_fee,
+            self.fee_mask,
+        )
+    }
+
+    /// Selects spendable outputs at `current_height`, from the largest, until they cover
+    /// `target` and the fee of a transaction spending them to two outputs, the payment and the
+    /// change, with rings of `ring_size` members.
+    ///
+    /// `per_kb_fee` and `fee_mask` are usually the ones of the response, or a tier of
+    /// [`UnspentOuts::fee_for_priority`]: servers report the fee per byte of weight despite the
+    /// name. Fails with [`LwsError::InsufficientFunds`] if the spendable outputs do not cover
+    /// `target` and the fee.
+    pub fn select(
+        &self,
+        target: u64,
+        per_kb_fee: u64,
+        fee_mask: u64,
+        current_height: u64,
+        ring_size: usize,
+    ) -> Result<Selection<'_>, LwsError> {
+        let fee = |n_inputs| {
+            fee_for_weight(
+                estimate_tx_weight(n_inputs, 2, ring_size),
+                per_kb_fee,
+                fee_mask,
+            )
+        };
+        let candidates: Vec<(u64, &Output)> = self
+            .sorted_by_amount_desc()
+            .into_iter()
+            .filter(|output| output.spendable_at(current_height))
+            .filter_map(|output| Some((output.amount.parse().ok()?, output)))
+            .filter(|(amount, _)| *amount > 0)
+            .collect();
+        let mut inputs = Vec::new();
+        let mut total = 0u64;
+        for (amount, output) in &candidates {
+            inputs.push(*output);
+            total = total.saturating_add(*amount);
+            let estimated_fee = fee(inputs.len());
+            if let Some(change) = total
+                .checked_sub(target)
+                .and_then(|rest| rest.checked_sub(estimated_fee))
+            {
+                return Ok(Selection {
+                    inputs,
+                    total,
+                    estimated_fee,
+                    change,
+                });
+            }
+        }
+        Err(LwsError::InsufficientFunds {
+            required: target.saturating_add(fee(candidates.len().max(1))),
+            available: total,
+        })
+    }
+
+    /// Outputs spendable at `current_height`, see [`Output::spendable_at`].
+    pub fn spendable_now(&self, current_height: u64) -> Vec<&Output> {
+        self.outputs
+            .iter()
+            .filter(|output| output.spendable_at(current_height))
+            .collect()
+    }
+}
+
+/// Outputs chosen by [`UnspentOuts::select`], amounts in piconero.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Selection<'a> {
+    /// Selected outputs, largest first.
+    pub inputs: Vec<&'a Output>,
+    /// Sum of the amounts of `inputs`.
+    pub total: u64,
+    pub estimated_fee: u64,
+    /// What is left of `total` after the target and the fee.
+    pub change: u64,
+}
+
+/// Fee of a transaction weighing `weight`, rounded up to a multiple of `fee_mask`.
+fn fee_for_weight(weight: u64, per_byte_fee: u64, fee_mask: u64) -> u64 {
+    let fee = weight.saturating_mul(per_byte_fee);
+    let mask = fee_mask.max(1);
+    fee.saturating_add(mask - 1) / mask * mask
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Output {
+    #[serde(alias = "txId")]
     pub tx_id: u64,
     pub amount: String,
     pub index: u16,
+    #[serde(alias = "globalIndex")]
     pub global_index: u64,
-    pub rct: String,
-    pub tx_hash: HashString<CryptoNoteHash>,
-    pub tx_prefix_hash: String,
-    pub public_key: HashString<CryptoNoteHash>,
-    pub tx_pub_key: HashString<CryptoNoteHash>,
-    pub spend_key_images: Vec<HashString<CryptoNoteHash>>,
+    /// RingCT commitment data, empty for pre-RingCT outputs.
+    #[serde(with = "hex_vec")]
+    pub rct: Vec<u8>,
+    #[serde(alias = "txHash")]
+    pub tx_hash: HashString<TxHash>,
+    #[serde(with = "hex_vec", alias = "txPrefixHash")]
+    pub tx_prefix_hash: Vec<u8>,
+    #[serde(alias = "publicKey")]
+    pub public_key: HashString<PublicKey>,
+    #[serde(alias = "txPubKey")]
+    pub tx_pub_key: HashString<PublicKey>,
+    #[serde(default, alias = "spendKeyImages")]
+    pub spend_key_images: Vec<HashString<KeyImage>>,
     pub timestamp: String,
     pub height: u64,
+    /// Height of the block spending the output, for servers reporting it. `None` when unspent
+    /// or not reported.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "spendHeight"
+    )]
+    pub spend_height: Option<u64>,
+    /// Unlock time of the transaction creating the output, for servers reporting it: a block
+    /// height below [`MAX_BLOCK_NUMBER`], a unix timestamp otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "unlockTime")]
+    pub unlock_time: Option<u64>,
+    /// Whether the output is a block reward, for servers reporting it, which can only be spent
+    /// after [`COINBASE_SPENDABLE_AGE`] confirmations. `false` when not reported.
+    #[serde(
+        default,
+        deserialize_with = "number_or_boolean",
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    pub coinbase: bool,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// Number of confirmations after which an output can be spent.
+pub const SPENDABLE_AGE: u64 = 10;
+/// Number of confirmations after which a coinbase output can be spent.
+pub const COINBASE_SPENDABLE_AGE: u64 = 60;
+/// Unlock times below this are block heights, unix timestamps otherwise.
+pub const MAX_BLOCK_NUMBER: u64 = 500_000_000;
+/// How early the daemon accepts spending an output locked until a timestamp, in seconds.
+const LOCKED_TX_ALLOWED_DELTA_SECONDS: u64 = 120;
+
+impl Output {
+    /// Whether this is a RingCT output, i.e. it has a commitment in `rct`.
+    ///
+    /// The amount of RingCT outputs is hidden in their commitment: `amount` is the value the
+    /// server decoded with the view key, and may be `"0"` when it did not.
+    pub fn is_rct(&self) -> bool {
+        !self.rct.is_empty()
+    }
+
+    /// `rct` as hex, as reported by the server.
+    pub fn rct_hex(&self) -> String {
+        hex::encode(&self.rct)
+    }
+
+    /// `tx_prefix_hash` as hex, as reported by the server.
+    pub fn tx_prefix_hash_hex(&self) -> String {
+        hex::encode(&self.tx_prefix_hash)
+    }
+
+    /// The amount of a pre-RingCT output, in piconero, which is public. `None` for RingCT
+    /// outputs, whose `amount` cannot be told apart from a hidden amount when it is `"0"`, and
+    /// for amounts that are zero or not integers.
+    pub fn clear_amount(&self) -> Option<u64> {
+        if self.is_rct() {
+            return None;
+        }
+        self.amount.parse().ok().filter(|amount| *amount > 0)
+    }
+
+    /// Whether `tx_prefix_hash` is the hash of the prefix of `tx`, the transaction creating the
+    /// output, as fetched from a trusted daemon by `tx_hash`.
+    ///
+    /// Light wallets build their transactions from the output metadata the server reports:
+    /// checking it against the transaction detects a server tampering with it, at the cost of
+    /// fetching the transaction from a daemon.
+    #[cfg(feature = "verify")]
+    pub fn verify_prefix_hash(&self, tx: &monero::Transaction) -> bool {
+        use monero::cryptonote::hash::Hashable;
+
+        self.tx_prefix_hash == tx.prefix().hash().as_bytes()
+    }
+
+    /// `amount` in XMR, see [`piconero_to_xmr`].
+    #[cfg(feature = "decimal")]
+    pub fn amount_xmr(&self) -> Result<Decimal, LwsError> {
+        parse_amount(&self.amount).map(piconero_to_xmr)
+    }
+
+    /// Whether the output can be spent in a transaction built at `current_height`, the height
+    /// of the latest block, i.e. it has [`SPENDABLE_AGE`] confirmations, or
+    /// [`COINBASE_SPENDABLE_AGE`] for coinbase outputs, and its unlock time, if the server
+    /// reports it, has passed. Timestamp unlock times are compared with the system time, see
+    /// [`Output::spendable_with`] for another clock.
+    ///
+    /// Coinbase outputs are only told apart when the server reports `coinbase` or their unlock
+    /// time: without either they may be reported as spendable early.
+    pub fn spendable_at(&self, current_height: u64) -> bool {
+        self.spendable_with(current_height, &SystemClock)
+    }
+
+    /// Like [`Output::spendable_at`], comparing timestamp unlock times with `clock`.
+    pub fn spendable_with(&self, current_height: u64, clock: &impl Clock) -> bool {
+        let age = if self.coinbase {
+            COINBASE_SPENDABLE_AGE
+        } else {
+            SPENDABLE_AGE
+        };
+        let mature = current_height
+            .checked_sub(self.height)
+            .is_some_and(|depth| depth + 1 >= age);
+        mature
+            && self
+                .unlock_time
+                .is_none_or(|unlock_time| is_unlocked(unlock_time, current_height, clock))
+    }
+}
+
+/// Whether `unlock_time` has passed for a transaction built at `current_height`, with the
+/// tolerance of the daemon.
+fn is_unlocked(unlock_time: u64, current_height: u64, clock: &impl Clock) -> bool {
+    if unlock_time < MAX_BLOCK_NUMBER {
+        current_height + 1 >= unlock_time
+    } else {
+        clock.now() + LOCKED_TX_ALLOWED_DELTA_SECONDS >= unlock_time
+    }
+}
+
+/// Everything needed to build a transaction, see [`crate::LwsRpcClient::prepare_spend`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpendContext {
+    /// Selected inputs, largest first.
+    pub outputs: Vec<Output>,
+    /// Decoys for the selected inputs.
+    pub decoys: AmountOuts,
+    pub per_kb_fee: u64,
+    pub fee_mask: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ImportResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub payment_address: Option<monero::Address>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub payment_id: Option<HashString<PaymentId>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub import_fee: Option<String>,
     #[serde(deserialize_with = "number_or_boolean")]
     pub new_request: bool,
@@ -206,11 +1240,83 @@ pub struct ImportResponse {
     pub status: String,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+impl ImportResponse {
+    /// `monero:` URI paying the import fee, e.g. for a QR code, see [`crate::payment_uri`]. `None`
+    /// when the server did not report the payment address or the fee.
+    pub fn payment_uri(&self) -> Option<String> {
+        let address = self.payment_address.as_ref()?;
+        let fee = self.import_fee.as_ref()?.parse().ok()?;
+        let payment_id = self
+            .payment_id
+            .as_ref()
+            .map(|payment_id| PaymentIdField::Short(payment_id.0));
+        Some(payment_uri(address, Some(fee), payment_id.as_ref(), None))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct LoginResponse {
     #[serde(deserialize_with = "number_or_boolean")]
     pub new_address: bool,
     #[serde(deserialize_with = "number_or_boolean")]
     pub generated_locally: bool,
+    /// Height from which the server scans the account. Servers report it when the account is
+    /// created (`new_address`), it may be `None` for existing accounts and on servers that do
+    /// not support restore heights.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub start_height: Option<u64>,
 }
+
+impl LoginResponse {
+    /// Height from which the account is scanned, `0` (the genesis block) when the server did
+    /// not report it.
+    pub fn restore_height(&self) -> u64 {
+        self.start_height.unwrap_or(0)
+    }
+
+    /// Whether scanning starts at or below the `requested` height, i.e. no transaction after it
+    /// is missed. `None` when the server did not report its start height.
+    pub fn honors_start_height(&self, requested: u64) -> Option<bool> {
+        self.start_height
+            .map(|start_height| start_height <= requested)
+    }
+}
+
+/// Matchable outcome of a login, e.g. to onboard new accounts. Converted from a
+/// [`LoginResponse`], or a reference to it to keep the response.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoginOutcome {
+    /// The server already knew the account.
+    ExistingAccount { start_height: Option<u64> },
+    /// The login created the account.
+    NewAccount {
+        start_height: Option<u64>,
+        generated_locally: bool,
+    },
+}
+
+impl From<&LoginResponse> for LoginOutcome {
+    fn from(login: &LoginResponse) -> Self {
+        if login.new_address {
+            LoginOutcome::NewAccount {
+                start_height: login.start_height,
+                generated_locally: login.generated_locally,
+            }
+        } else {
+            LoginOutcome::ExistingAccount {
+                start_height: login.start_height,
+            }
+        }
+    }
+}
+
+impl From<LoginResponse> for LoginOutcome {
+    fn from(login: LoginResponse) -> Self {
+        Self::from(&login)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SubmitRawTxResponse {
+    pub status: String,
+}
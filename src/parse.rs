@@ -0,0 +1,120 @@
+// Rust Monero Light Wallet Server RPC Client
+// Written in 2021-2022 by
+//   Sebastian Kung <seb.kung@gmail.com>
+//   Monero Rust Contributors
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Parsing of raw response bodies, for responses obtained without [`crate::LwsRpcClient`], e.g.
+//! from a proxy or a log. Failed statuses are reported as [`LwsError::Server`], like the client
+//! does.
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{
+    AddressInfo, AddressTxs, AmountOuts, ImportResponse, LoginResponse, LwsError, ServerFailure,
+    SubmitRawTxResponse, UnspentOuts, FAILED_STATUSES,
+};
+
+/// Parses the body of any response into `T`.
+pub fn parse<T: DeserializeOwned>(body: &[u8]) -> Result<T, LwsError> {
+    from_value(parse_value(body)?)
+}
+
+pub fn parse_address_info(body: &[u8]) -> Result<AddressInfo, LwsError> {
+    parse(body)
+}
+
+pub fn parse_address_txs(body: &[u8]) -> Result<AddressTxs, LwsError> {
+    parse(body)
+}
+
+pub fn parse_random_outs(body: &[u8]) -> Result<AmountOuts, LwsError> {
+    parse(body)
+}
+
+pub fn parse_unspent_outs(body: &[u8]) -> Result<UnspentOuts, LwsError> {
+    parse(body)
+}
+
+pub fn parse_import_response(body: &[u8]) -> Result<ImportResponse, LwsError> {
+    parse(body)
+}
+
+pub fn parse_login_response(body: &[u8]) -> Result<LoginResponse, LwsError> {
+    parse(body)
+}
+
+pub fn parse_submit_raw_tx(body: &[u8]) -> Result<SubmitRawTxResponse, LwsError> {
+    parse(body)
+}
+
+/// Parses `body` as JSON, failing if its `status` is a failure.
+pub(crate) fn parse_value(body: &[u8]) -> Result<Value, LwsError> {
+    check_status(parse_json(body)?)
+}
+
+/// Parses `body` as a JSON-RPC 2.0 response, failing with [`LwsError::Server`] on an `error`
+/// object, and returns its `result` like [`parse_value`].
+pub(crate) fn parse_jsonrpc_value(body: &[u8]) -> Result<Value, LwsError> {
+    let mut value = parse_json(body)?;
+    if let Some(error) = value.get("error").filter(|error| !error.is_null()) {
+        let status = match error.get("code") {
+            Some(code) => code.to_string(),
+            None => "error".to_string(),
+        };
+        let reason = error
+            .get("message")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        return Err(LwsError::server(status, reason));
+    }
+    match value.get_mut("result") {
+        Some(result) => check_status(result.take()),
+        None => Err(LwsError::Deserialize {
+            path: None,
+            source: serde::de::Error::missing_field("result"),
+        }),
+    }
+}
+
+fn parse_json(body: &[u8]) -> Result<Value, LwsError> {
+    serde_json::from_slice(body).map_err(|source| LwsError::Deserialize { path: None, source })
+}
+
+fn check_status(value: Value) -> Result<Value, LwsError> {
+    if let Some(status) = value.get("status").and_then(Value::as_str) {
+        if FAILED_STATUSES.contains(&status) {
+            let failure: ServerFailure = from_value(value.clone())?;
+            return Err(LwsError::server(status.to_string(), failure.reason));
+        }
+    }
+    Ok(value)
+}
+
+#[cfg(not(feature = "error-paths"))]
+pub(crate) fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, LwsError> {
+    serde_json::from_value(value).map_err(|source| LwsError::Deserialize { path: None, source })
+}
+
+#[cfg(feature = "error-paths")]
+pub(crate) fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, LwsError> {
+    serde_path_to_error::deserialize(value).map_err(|err| {
+        let path = err.path().to_string();
+        LwsError::Deserialize {
+            // `.` is the root, i.e. the error is not about a field
+            path: Some(path).filter(|path| path != "."),
+            source: err.into_inner(),
+        }
+    })
+}
@@ -0,0 +1,200 @@
+// Rust Monero Light Wallet Server RPC Client
+// Written in 2021-2022 by
+//   Sebastian Kung <seb.kung@gmail.com>
+//   Monero Rust Contributors
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Recording of the traffic of a [`crate::LwsRpcClient`], see
+//! [`crate::LwsRpcClientBuilder::record_to`], and its replay with [`ReplayTransport`], to
+//! reproduce the quirks of a server in tests.
+//!
+//! Recordings are JSON lines, one [`Interaction`] per request. Headers are not recorded, and
+//! the view key sent with requests is redacted unless
+//! [`crate::LwsRpcClientBuilder::record_view_keys`] is set.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{HeaderMap, HttpRequest, HttpResponse, HttpTransport, StatusCode, TransportFuture};
+
+/// Fields of request bodies replaced with [`REDACTED`] in recordings.
+pub const REDACTED_FIELDS: &[&str] = &["view_key"];
+/// Value of the redacted fields.
+pub const REDACTED: &str = "<redacted>";
+
+/// A request and the response of the server to it. Bodies are recorded as text, so requests
+/// should not be compressed while recording.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    /// Path of the endpoint, e.g. `/login`.
+    pub path: String,
+    pub request: String,
+    pub status: u16,
+    pub response: String,
+}
+
+/// Forwards requests to another transport, appending every interaction to a file.
+#[derive(Debug)]
+pub(crate) struct RecordingTransport {
+    inner: Arc<dyn HttpTransport>,
+    file: Mutex<File>,
+    redact: bool,
+}
+
+impl RecordingTransport {
+    pub(crate) fn new(
+        inner: Arc<dyn HttpTransport>,
+        path: &Path,
+        redact: bool,
+    ) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            inner,
+            file: Mutex::new(file),
+            redact,
+        })
+    }
+
+    fn write(&self, interaction: &Interaction) -> anyhow::Result<()> {
+        let mut line = serde_json::to_string(interaction)?;
+        line.push('\n');
+        let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl HttpTransport for RecordingTransport {
+    fn post(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let path = url_path(&request.url);
+            let mut body = String::from_utf8_lossy(&request.body).into_owned();
+            if self.redact {
+                body = redact(&body);
+            }
+            let response = self.inner.post(request).await?;
+            self.write(&Interaction {
+                path,
+                request: body,
+                status: response.status.as_u16(),
+                response: String::from_utf8_lossy(&response.body).into_owned(),
+            })?;
+            Ok(response)
+        })
+    }
+}
+
+/// Answers requests with the responses of a recording, matching them by path and body, with
+/// the redacted fields ignored. Identical requests, e.g. polls, get the matching responses in
+/// recording order, the last one being repeated once they are exhausted. Requests without a
+/// match fail.
+#[derive(Debug)]
+pub struct ReplayTransport {
+    interactions: Vec<Interaction>,
+    served: Mutex<Vec<bool>>,
+}
+
+impl ReplayTransport {
+    pub fn new(interactions: Vec<Interaction>) -> Self {
+        let served = Mutex::new(vec![false; interactions.len()]);
+        Self {
+            interactions,
+            served,
+        }
+    }
+
+    /// Reads a recording written with [`crate::LwsRpcClientBuilder::record_to`].
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let mut interactions = Vec::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                interactions.push(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(Self::new(interactions))
+    }
+
+    pub fn interactions(&self) -> &[Interaction] {
+        &self.interactions
+    }
+
+    fn find(&self, path: &str, body: &str) -> Option<&Interaction> {
+        let body = normalize(body);
+        let mut served = self.served.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut last = None;
+        for (i, interaction) in self.interactions.iter().enumerate() {
+            if interaction.path != path || normalize(&interaction.request) != body {
+                continue;
+            }
+            if !served[i] {
+                served[i] = true;
+                return Some(interaction);
+            }
+            last = Some(interaction);
+        }
+        last
+    }
+}
+
+impl HttpTransport for ReplayTransport {
+    fn post(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let path = url_path(&request.url);
+            let body = String::from_utf8_lossy(&request.body);
+            let interaction = self.find(&path, &body).ok_or_else(|| {
+                anyhow::anyhow!("no recorded response for {} with body {}", path, body)
+            })?;
+            Ok(HttpResponse {
+                status: StatusCode::from_u16(interaction.status)?,
+                headers: HeaderMap::new(),
+                body: interaction.response.clone().into_bytes(),
+            })
+        })
+    }
+}
+
+fn url_path(url: &str) -> String {
+    reqwest::Url::parse(url).map_or_else(|_| url.to_string(), |url| url.path().to_string())
+}
+
+/// `body` with the values of [`REDACTED_FIELDS`] replaced, if it is a JSON object.
+fn redact(body: &str) -> String {
+    match redact_value(body) {
+        Some(value) => value.to_string(),
+        None => body.to_string(),
+    }
+}
+
+fn redact_value(body: &str) -> Option<Value> {
+    let mut value: Value = serde_json::from_str(body).ok()?;
+    let object = value.as_object_mut()?;
+    for field in REDACTED_FIELDS {
+        if let Some(secret) = object.get_mut(*field) {
+            *secret = REDACTED.into();
+        }
+    }
+    Some(value)
+}
+
+// Compares JSON bodies regardless of their formatting and redaction
+fn normalize(body: &str) -> Value {
+    redact_value(body).unwrap_or_else(|| Value::String(body.to_string()))
+}
@@ -0,0 +1,183 @@
+// Rust Monero Light Wallet Server RPC Client
+// Written in 2021-2022 by
+//   Sebastian Kung <seb.kung@gmail.com>
+//   Monero Rust Contributors
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Higher level sync loop, see [`SyncSession`].
+
+use std::collections::HashMap;
+
+use crate::{Account, ImportResponse, LoginResponse, Transaction, TxHash};
+
+/// Stage of a [`SyncSession`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncState {
+    /// The account is not logged in yet.
+    NotLoggedIn,
+    /// The account is logged in, the import from the requested height is not requested yet.
+    Importing,
+    /// The server is scanning the account.
+    Scanning,
+    /// The server scanned the account up to the top of the chain.
+    Synced,
+}
+
+/// What happened during a [`SyncSession::step`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum SyncEvent {
+    LoggedIn(LoginResponse),
+    ImportRequested(ImportResponse),
+    /// Heights reported by [`Account::info`].
+    Progress {
+        scanned_block_height: u64,
+        blockchain_height: u64,
+    },
+    /// Transactions not reported by an earlier step, in the server order.
+    NewTransactions(Vec<Transaction>),
+    /// Transactions reported by an earlier step that were mined since, e.g. out of the
+    /// mempool, or mined again at another height after a reorganization.
+    Confirmed(Vec<Transaction>),
+    /// The account became fully scanned.
+    Synced,
+}
+
+/// Drives an account through login, import and scanning, emitting the progress and the new
+/// transactions on the way:
+///
+/// [`NotLoggedIn`] → [`Importing`] (only with [`SyncSession::import_from`]) → [`Scanning`] →
+/// [`Synced`]
+///
+/// Each [`SyncSession::step`] makes one transition, or one poll once scanning; call it in a
+/// loop with a delay between polls. A synced session keeps polling for new transactions and
+/// goes back to [`Scanning`] when the server falls behind the chain. Polls only look at the
+/// transactions mined above the height scanned at the previous poll, and at the mempool.
+///
+/// [`NotLoggedIn`]: SyncState::NotLoggedIn
+/// [`Importing`]: SyncState::Importing
+/// [`Scanning`]: SyncState::Scanning
+/// [`Synced`]: SyncState::Synced
+#[derive(Clone, Debug)]
+pub struct SyncSession {
+    account: Account,
+    state: SyncState,
+    create_account: bool,
+    generated_locally: bool,
+    import_from: Option<u64>,
+    /// Height of the reported transactions, `None` for the mempool.
+    seen: HashMap<TxHash, Option<u64>>,
+    /// Transactions mined below are not looked at anymore.
+    since_height: u64,
+}
+
+impl SyncSession {
+    pub fn new(account: Account) -> Self {
+        Self {
+            account,
+            state: SyncState::NotLoggedIn,
+            create_account: true,
+            generated_locally: false,
+            import_from: None,
+            seen: HashMap::new(),
+            since_height: 0,
+        }
+    }
+
+    /// Whether the login creates the account if the server does not know it, see
+    /// [`Account::login`]. On if not set.
+    pub fn create_account(mut self, create_account: bool) -> Self {
+        self.create_account = create_account;
+        self
+    }
+
+    /// Whether the account was created by this wallet rather than restored, see
+    /// [`Account::login`]. Off if not set.
+    pub fn generated_locally(mut self, generated_locally: bool) -> Self {
+        self.generated_locally = generated_locally;
+        self
+    }
+
+    /// Requests the server to scan the account from `height`, e.g. the restore height of a
+    /// restored wallet, see [`Account::import_request`]. Not requested if not set.
+    pub fn import_from(mut self, height: u64) -> Self {
+        self.import_from = Some(height);
+        self
+    }
+
+    pub fn account(&self) -> &Account {
+        &self.account
+    }
+
+    pub fn state(&self) -> SyncState {
+        self.state
+    }
+
+    /// Moves the session forward by one call to the server, or two once scanning, and returns
+    /// what happened. On failure the state is unchanged, so the step can be retried.
+    pub async fn step(&mut self) -> anyhow::Result<Vec<SyncEvent>> {
+        match self.state {
+            SyncState::NotLoggedIn => {
+                let login = self
+                    .account
+                    .login(self.create_account, self.generated_locally)
+                    .await?;
+                self.state = match self.import_from {
+                    Some(_) => SyncState::Importing,
+                    None => SyncState::Scanning,
+                };
+                Ok(vec![SyncEvent::LoggedIn(login)])
+            }
+            SyncState::Importing => {
+                let import = self.account.import_request(self.import_from).await?;
+                self.state = SyncState::Scanning;
+                Ok(vec![SyncEvent::ImportRequested(import)])
+            }
+            SyncState::Scanning | SyncState::Synced => {
+                let refresh = self.account.refresh(self.since_height).await?;
+                let mut events = vec![SyncEvent::Progress {
+                    scanned_block_height: refresh.info.scanned_block_height,
+                    blockchain_height: refresh.info.blockchain_height,
+                }];
+                let mut new_txs = Vec::new();
+                let mut confirmed = Vec::new();
+                for tx in refresh.new_txs {
+                    match self.seen.insert(tx.hash.0, tx.height) {
+                        None => new_txs.push(tx),
+                        Some(height) if height != tx.height && tx.height.is_some() => {
+                            confirmed.push(tx)
+                        }
+                        Some(_) => {}
+                    }
+                }
+                if !new_txs.is_empty() {
+                    events.push(SyncEvent::NewTransactions(new_txs));
+                }
+                if !confirmed.is_empty() {
+                    events.push(SyncEvent::Confirmed(confirmed));
+                }
+                // after a reorganization, the blocks above the scanned height are mined again
+                self.since_height = refresh.info.scanned_block_height.saturating_add(1);
+                let synced = refresh.info.is_fully_scanned();
+                if synced && self.state == SyncState::Scanning {
+                    events.push(SyncEvent::Synced);
+                }
+                self.state = if synced {
+                    SyncState::Synced
+                } else {
+                    SyncState::Scanning
+                };
+                Ok(events)
+            }
+        }
+    }
+}
@@ -0,0 +1,96 @@
+// Rust Monero Light Wallet Server RPC Client
+// Written in 2021-2022 by
+//   Sebastian Kung <seb.kung@gmail.com>
+//   Monero Rust Contributors
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Request metrics, recorded through the [`metrics`] facade when the `metrics` feature is
+//! enabled:
+//!
+//! - `lws_requests_total`: requests issued, labeled by `endpoint`
+//! - `lws_requests_succeeded_total`: calls that returned a response, i.e. answered
+//!   successfully and parsed into the expected model, labeled by `endpoint`
+//! - `lws_requests_failed_total`: failed calls, whether the request, the server or the parsing
+//!   of the response failed, labeled by `endpoint` and error `kind`
+//! - `lws_request_duration_seconds`: latency histogram, labeled by `endpoint`
+//! - `lws_deserialize_failures_total`: responses with an invalid field, labeled by `endpoint`
+//!   and `field`, with the `error-paths` feature. Array indices are left out of the field, e.g.
+//!   `outputs[].amount`, so that the label only takes values from the models
+
+use std::time::Duration;
+
+use crate::LwsError;
+
+/// Records the outcome of a call, once its response is parsed into the expected model.
+pub(crate) fn record<T>(endpoint: &str, elapsed: Duration, result: &anyhow::Result<T>) {
+    let endpoint = endpoint.to_string();
+    ::metrics::counter!("lws_requests_total", "endpoint" => endpoint.clone()).increment(1);
+    ::metrics::histogram!("lws_request_duration_seconds", "endpoint" => endpoint.clone())
+        .record(elapsed.as_secs_f64());
+    match result {
+        Ok(_) => {
+            ::metrics::counter!("lws_requests_succeeded_total", "endpoint" => endpoint).increment(1)
+        }
+        Err(err) => {
+            if let Some(LwsError::Deserialize {
+                path: Some(path), ..
+            }) = err.downcast_ref::<LwsError>()
+            {
+                ::metrics::counter!(
+                    "lws_deserialize_failures_total",
+                    "endpoint" => endpoint.clone(),
+                    "field" => field_label(path),
+                )
+                .increment(1);
+            }
+            ::metrics::counter!(
+                "lws_requests_failed_total",
+                "endpoint" => endpoint,
+                "kind" => error_kind(err),
+            )
+            .increment(1)
+        }
+    }
+}
+
+// Drops the array indices of `path`, which depend on the response
+fn field_label(path: &str) -> String {
+    let mut label = String::with_capacity(path.len());
+    let mut in_index = false;
+    for c in path.chars() {
+        match c {
+            '[' => in_index = true,
+            ']' => in_index = false,
+            c if in_index && c.is_ascii_digit() => continue,
+            _ => {}
+        }
+        label.push(c);
+    }
+    label
+}
+
+fn error_kind(err: &anyhow::Error) -> &'static str {
+    if let Some(err) = err.downcast_ref::<LwsError>() {
+        match err {
+            LwsError::Server { .. } => "server",
+            LwsError::AccountNotFound { .. } => "account_not_found",
+            LwsError::HttpStatus { .. } => "http_status",
+            LwsError::ResponseTooLarge { .. } | LwsError::ResponseTooDeep { .. } => "limit",
+            LwsError::Http { .. } => "transport",
+            LwsError::Deserialize { .. } => "deserialize",
+            _ => "other",
+        }
+    } else {
+        "other"
+    }
+}
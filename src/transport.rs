@@ -0,0 +1,136 @@
+// Rust Monero Light Wallet Server RPC Client
+// Written in 2021-2022 by
+//   Sebastian Kung <seb.kung@gmail.com>
+//   Monero Rust Contributors
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+use std::{fmt::Debug, future::Future, pin::Pin};
+
+pub use reqwest::{header::HeaderMap, StatusCode};
+
+use reqwest::header::{RETRY_AFTER, SERVER};
+
+use crate::LwsError;
+
+/// Future returned by [`HttpTransport::post`].
+pub type TransportFuture<'a> =
+    Pin<Box<dyn Future<Output = anyhow::Result<HttpResponse>> + Send + 'a>>;
+
+/// A request to post to the server.
+#[derive(Clone, Debug)]
+pub struct HttpRequest {
+    pub url: String,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+/// The raw answer of the server, whatever its status.
+#[derive(Clone, Debug)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+/// Details of a successful response, see [`crate::LwsRpcClient::call_with_meta`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResponseMeta {
+    pub status: StatusCode,
+    /// The `Retry-After` header, how long to wait before the next request when rate limited.
+    pub retry_after: Option<String>,
+    /// The `Server` header, typically the server software and its version.
+    pub server: Option<String>,
+}
+
+impl ResponseMeta {
+    pub(crate) fn from_headers(status: StatusCode, headers: &HeaderMap) -> Self {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            status,
+            retry_after: header(RETRY_AFTER),
+            server: header(SERVER),
+        }
+    }
+}
+
+/// Sends the requests of a [`crate::LwsRpcClient`]. Implement it to use another HTTP library or
+/// to answer in-process, e.g. in tests.
+///
+/// Transports only move bytes: unsuccessful statuses must be returned as responses, the client
+/// takes care of turning them into errors.
+pub trait HttpTransport: Debug + Send + Sync {
+    fn post(&self, request: HttpRequest) -> TransportFuture<'_>;
+}
+
+/// Default transport, based on [`reqwest`].
+#[derive(Clone, Debug)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+    max_response_bytes: usize,
+}
+
+impl ReqwestTransport {
+    /// Sends requests with `client`, buffering at most `max_response_bytes` of each response.
+    pub fn new(client: reqwest::Client, max_response_bytes: usize) -> Self {
+        Self {
+            client,
+            max_response_bytes,
+        }
+    }
+
+    /// Buffers the response body, giving up as soon as it exceeds `max_response_bytes`.
+    async fn read_body(&self, rsp: &mut reqwest::Response) -> anyhow::Result<Vec<u8>> {
+        let limit = self.max_response_bytes;
+        if rsp.content_length().is_some_and(|len| len > limit as u64) {
+            return Err(LwsError::ResponseTooLarge { limit }.into());
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = rsp.chunk().await.map_err(http_error)? {
+            if body.len() + chunk.len() > limit {
+                return Err(LwsError::ResponseTooLarge { limit }.into());
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body)
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn post(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let mut rsp = self
+                .client
+                .post(request.url)
+                .headers(request.headers)
+                .body(request.body)
+                .send()
+                .await
+                .map_err(http_error)?;
+            let body = self.read_body(&mut rsp).await?;
+            Ok(HttpResponse {
+                status: rsp.status(),
+                headers: rsp.headers().clone(),
+                body,
+            })
+        })
+    }
+}
+
+fn http_error(source: reqwest::Error) -> LwsError {
+    LwsError::Http { source }
+}
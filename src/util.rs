@@ -14,8 +14,287 @@
 // copies or substantial portions of the Software.
 //
 
+use crate::LwsError;
 use serde::{Deserialize, Deserializer, Serialize};
-use std::fmt::{self, Display};
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Parses a Monero address, keeping the offending string in the error.
+pub fn parse_address(address: &str) -> Result<monero::Address, LwsError> {
+    monero::Address::from_str(address).map_err(|err| LwsError::InvalidInput {
+        input: address.to_string(),
+        source: Box::new(err),
+    })
+}
+
+/// The network `address` belongs to.
+pub fn detect_network(address: &monero::Address) -> monero::Network {
+    address.network
+}
+
+/// Exact value in XMR of `piconero`, with 12 decimals.
+#[cfg(feature = "decimal")]
+pub fn piconero_to_xmr(piconero: u64) -> rust_decimal::Decimal {
+    rust_decimal::Decimal::from_i128_with_scale(i128::from(piconero), 12)
+}
+
+/// Builds the integrated address combining the standard address `address` with the short payment
+/// id `payment_id`, e.g. to match a payment to the invoice that requested it.
+///
+/// Only 8 byte payment ids can be integrated, which [`PaymentId`] guarantees, and only into a
+/// standard address: subaddresses and integrated addresses fail with
+/// [`LwsError::InvalidInput`].
+///
+/// [`PaymentId`]: monero::util::address::PaymentId
+pub fn make_integrated_address(
+    address: &monero::Address,
+    payment_id: &monero::util::address::PaymentId,
+) -> Result<monero::Address, LwsError> {
+    if address.addr_type != monero::AddressType::Standard {
+        return Err(LwsError::InvalidInput {
+            input: address.to_string(),
+            source: "only standard addresses can be integrated".into(),
+        });
+    }
+    Ok(monero::Address::integrated(
+        address.network,
+        address.public_spend,
+        address.public_view,
+        *payment_id,
+    ))
+}
+
+/// A payment id as found in a transaction: a short, 8 byte one, which is encrypted and can be
+/// integrated into an address, or a deprecated long, 32 byte one, which is not.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PaymentIdField {
+    Short(monero::util::address::PaymentId),
+    Long(monero::Hash),
+}
+
+impl PaymentIdField {
+    /// Parses a payment id, short or long depending on its length: 16 or 64 hex characters.
+    /// Other lengths fail with [`LwsError::InvalidInput`].
+    pub fn from_hex(payment_id: &str) -> Result<Self, LwsError> {
+        let invalid = |source: Box<dyn std::error::Error + Send + Sync>| LwsError::InvalidInput {
+            input: payment_id.to_string(),
+            source,
+        };
+        let bytes = hex::decode(payment_id).map_err(|err| invalid(Box::new(err)))?;
+        match bytes.len() {
+            8 => Ok(PaymentIdField::Short(
+                monero::util::address::PaymentId::from_slice(&bytes),
+            )),
+            32 => Ok(PaymentIdField::Long(monero::Hash::from_slice(&bytes))),
+            _ => Err(invalid("payment ids are 8 or 32 bytes long".into())),
+        }
+    }
+
+    /// Length in bytes, 8 or 32.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    pub fn is_short(&self) -> bool {
+        matches!(self, PaymentIdField::Short(_))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            PaymentIdField::Short(payment_id) => payment_id.as_bytes(),
+            PaymentIdField::Long(payment_id) => payment_id.as_bytes(),
+        }
+    }
+}
+
+impl Display for PaymentIdField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.as_bytes()))
+    }
+}
+
+impl FromStr for PaymentIdField {
+    type Err = LwsError;
+
+    fn from_str(payment_id: &str) -> Result<Self, Self::Err> {
+        Self::from_hex(payment_id)
+    }
+}
+
+impl Serialize for PaymentIdField {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for PaymentIdField {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Self::from_hex(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Builds a `monero:` payment URI, e.g. to show as a QR code on a receive screen, requesting
+/// `amount` piconero to `address`. The amount is written in XMR, without trailing zeros, and the
+/// description is percent-encoded.
+pub fn payment_uri(
+    address: &monero::Address,
+    amount: Option<u64>,
+    payment_id: Option<&PaymentIdField>,
+    description: Option<&str>,
+) -> String {
+    let mut params = Vec::new();
+    if let Some(amount) = amount {
+        let fraction = format!("{:012}", amount % 1_000_000_000_000);
+        let fraction = fraction.trim_end_matches('0');
+        let mut xmr = (amount / 1_000_000_000_000).to_string();
+        if !fraction.is_empty() {
+            xmr.push('.');
+            xmr.push_str(fraction);
+        }
+        params.push(format!("tx_amount={}", xmr));
+    }
+    if let Some(payment_id) = payment_id {
+        params.push(format!("tx_payment_id={}", payment_id));
+    }
+    if let Some(description) = description {
+        params.push(format!("tx_description={}", percent_encode(description)));
+    }
+    let mut uri = format!("monero:{}", address);
+    if !params.is_empty() {
+        uri.push('?');
+        uri.push_str(&params.join("&"));
+    }
+    uri
+}
+
+// Keeps the unreserved characters of RFC 3986 only
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Key offsets of a ring, as serialized in a transaction input, from the global indices of its
+/// members, in any order: the indices are sorted and each offset is relative to the previous
+/// one, the first one being absolute.
+pub fn to_relative_offsets(indices: &[u64]) -> Vec<u64> {
+    let mut indices = indices.to_vec();
+    indices.sort_unstable();
+    let mut previous = 0;
+    indices
+        .into_iter()
+        .map(|index| {
+            let offset = index - previous;
+            previous = index;
+            offset
+        })
+        .collect()
+}
+
+/// Global indices of the members of a ring, sorted, from its relative key offsets, the inverse
+/// of [`to_relative_offsets`].
+pub fn from_relative_offsets(offsets: &[u64]) -> Vec<u64> {
+    offsets
+        .iter()
+        .scan(0u64, |index, offset| {
+            *index = index.saturating_add(*offset);
+            Some(*index)
+        })
+        .collect()
+}
+
+/// Estimates the weight, in bytes, of a transaction spending `n_inputs` to `n_outputs` with
+/// rings of `ring_size` members, to compute its fee before building it.
+///
+/// This follows the estimate of the reference wallet for current transactions: RingCT with
+/// CLSAG signatures, a single Bulletproofs+ range proof and view tags, and an extra field with
+/// the transaction public key and an encrypted payment id. Key offsets are assumed to take 2
+/// bytes each, so the estimate is usually within a few bytes of the real weight. Transactions
+/// with more than 2 outputs weigh more than their size, which is accounted for.
+pub fn estimate_tx_weight(n_inputs: usize, n_outputs: usize, ring_size: usize) -> u64 {
+    // version and unlock time, inputs with their key offsets and key image, outputs with their
+    // view tag, extra with the transaction public key and a payment id
+    let mut size = 1 + 6 + n_inputs * (1 + 6 + ring_size * 2 + 32) + n_outputs * (6 + 32 + 1) + 44;
+    // RingCT type, range proof, CLSAGs, pseudo outputs, encrypted amounts, commitments, fee
+    let mut log_padded_outputs = 0;
+    while (1 << log_padded_outputs) < n_outputs {
+        log_padded_outputs += 1;
+    }
+    size += 1 + (2 * (6 + log_padded_outputs) + 6) * 32 + 3;
+    size += n_inputs * (32 * ring_size + 64) + 32 * n_inputs + 8 * n_outputs + 32 * n_outputs + 4;
+    // proofs of more than 2 outputs weigh more than their size
+    if n_outputs > 2 {
+        let bp_base = 32 * (6 + 7 * 2) / 2;
+        let log_padded_outputs = log_padded_outputs.max(2);
+        let bp_size = 32 * (6 + 2 * (6 + log_padded_outputs));
+        size += (bp_base * (1 << log_padded_outputs) - bp_size) * 4 / 5;
+    }
+    size as u64
+}
+
+/// Source of the current time for the time-dependent helpers, e.g.
+/// [`crate::models::Output::spendable_with`]. Implement it to make them deterministic in tests.
+pub trait Clock {
+    /// Current unix time, in seconds.
+    fn now(&self) -> u64;
+}
+
+/// The system clock, used by default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs())
+    }
+}
+
+/// Pretty printed JSON of a response, more readable than its `Debug` output, e.g. to paste
+/// into a bug report.
+pub trait ToJsonPretty {
+    fn to_json_pretty(&self) -> Result<String, serde_json::Error>;
+}
+
+impl<T: Serialize> ToJsonPretty for T {
+    fn to_json_pretty(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// Serde helpers for variable-length hex byte fields, e.g. `#[serde(with = "hex_vec")]` on a
+/// `Vec<u8>`: invalid hex fails at parse time, and an empty string is no bytes.
+pub mod hex_vec {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        hex::decode(s).map_err(serde::de::Error::custom)
+    }
+}
 
 pub trait HashType: Sized {
     fn bytes(&self) -> &[u8];
@@ -47,7 +326,7 @@ impl HashType for Vec<u8> {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct HashString<T>(pub T);
 
 impl<T> Display for HashString<T>
@@ -0,0 +1,23 @@
+use std::str::FromStr;
+
+use monero_lws::{Account, LwsRpcClient};
+
+#[test]
+fn test_account_from_view_pair() {
+    let address = monero::Address::from_str("888tNkZrPN6JsEgekjMnABU4TBzc2Dt29EPAvkRxbANsAnjyPbb3iQ1YBRk1UXcdRsiKc9dhwMVgN5S9cQUiyoogDavup3H").unwrap();
+    let view_key = monero::PrivateKey::from_str(
+        "c2c5bf0e6c1a43f5e0c4a4b7ba7c1b8a3c8e7ab5a3e2f8a4c5d6e7f8091a2b0c",
+    )
+    .unwrap();
+    let view_pair = monero::ViewPair {
+        view: view_key,
+        spend: address.public_spend,
+    };
+    let client = LwsRpcClient::new("http://localhost:38884".into(), None);
+    let account = Account::from_view_pair(client, monero::Network::Mainnet, &view_pair);
+    assert_eq!(account.address().public_spend, address.public_spend);
+    assert_eq!(
+        account.address().public_view,
+        monero::PublicKey::from_private_key(&view_pair.view)
+    );
+}
@@ -0,0 +1,1409 @@
+use std::{
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use monero_lws::{
+    HeaderMap, HttpRequest, HttpResponse, HttpTransport, KeyImage, LoginResponse, LwsError,
+    LwsRpcClient, LwsRpcClientBuilder, StatusCode, SyncEvent, SyncSession, SyncState,
+    TransportFuture,
+};
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+use tokio_util::sync::CancellationToken;
+
+const ADDRESS: &str = "888tNkZrPN6JsEgekjMnABU4TBzc2Dt29EPAvkRxbANsAnjyPbb3iQ1YBRk1UXcdRsiKc9dhwMVgN5S9cQUiyoogDavup3H";
+const VIEW_KEY: &str = "c2c5bf0e6c1a43f5e0c4a4b7ba7c1b8a3c8e7ab5a3e2f8a4c5d6e7f8091a2b0c";
+
+/// Minimal HTTP server answering every request with `status` and `body`. Returns its base url
+/// and the raw requests it received.
+async fn mock_server(status: u16, body: String) -> (String, Arc<Mutex<Vec<String>>>) {
+    mock_router(move |_| (status, body.clone())).await
+}
+
+/// Minimal HTTP server answering each request with the status and body `route` returns for its
+/// path, or closing the connection for status 0. Returns its base url and the raw requests it received.
+async fn mock_router<F>(route: F) -> (String, Arc<Mutex<Vec<String>>>)
+where
+    F: Fn(&str) -> (u16, String) + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let received = requests.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length = text
+                        .lines()
+                        .find_map(|line| {
+                            line.to_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().parse::<usize>().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + length {
+                        break;
+                    }
+                }
+            }
+            let request = String::from_utf8_lossy(&request).to_string();
+            let path = request.split_whitespace().nth(1).unwrap_or_default();
+            let (status, body) = route(path);
+            received.lock().unwrap().push(request);
+            // status 0 resets the connection without answering
+            if status == 0 {
+                continue;
+            }
+            let response = format!(
+                "HTTP/1.1 {} OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+    (addr, requests)
+}
+
+fn credentials() -> (monero::Address, monero::PrivateKey) {
+    (
+        monero::Address::from_str(ADDRESS).unwrap(),
+        monero::PrivateKey::from_str(VIEW_KEY).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn test_max_response_bytes() {
+    let body = include_str!("fixtures/get_address_info.json").to_string();
+    let (addr, _) = mock_server(200, body.clone()).await;
+    let (address, view_key) = credentials();
+
+    let client = LwsRpcClientBuilder::new()
+        .max_response_bytes(body.len())
+        .build(addr.clone())
+        .unwrap();
+    client.get_address_info(address, view_key).await.unwrap();
+
+    let client = LwsRpcClientBuilder::new()
+        .max_response_bytes(body.len() - 1)
+        .build(addr)
+        .unwrap();
+    let err = client
+        .get_address_info(address, view_key)
+        .await
+        .unwrap_err();
+    match err.downcast_ref::<LwsError>() {
+        Some(LwsError::ResponseTooLarge { limit }) => assert_eq!(*limit, body.len() - 1),
+        _ => panic!("expected response too large, got {:?}", err),
+    }
+}
+
+#[tokio::test]
+async fn test_max_json_depth() {
+    let (address, view_key) = credentials();
+    let body = format!(
+        r#"{{"status":"OK","nested":{}"[{{"{}}}"#,
+        "[".repeat(100_000),
+        "]".repeat(100_000)
+    );
+    let (addr, _) = mock_server(200, body).await;
+    let client = LwsRpcClient::new(addr, None);
+    let err = client
+        .get_address_info(address, view_key)
+        .await
+        .unwrap_err();
+    match err.downcast_ref::<LwsError>() {
+        Some(LwsError::ResponseTooDeep { limit }) => assert_eq!(*limit, 64),
+        _ => panic!("expected response too deep, got {:?}", err),
+    }
+
+    let body = include_str!("fixtures/get_address_info.json").to_string();
+    let (addr, _) = mock_server(200, body).await;
+    let client = LwsRpcClientBuilder::new()
+        .max_json_depth(3)
+        .build(addr.clone())
+        .unwrap();
+    client.get_address_info(address, view_key).await.unwrap();
+    let client = LwsRpcClientBuilder::new()
+        .max_json_depth(2)
+        .build(addr)
+        .unwrap();
+    let err = client
+        .get_address_info(address, view_key)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "response nests deeper than the limit of 2 levels"
+    );
+}
+
+#[tokio::test]
+async fn test_shared_client_across_tasks() {
+    fn assert_shareable<T: Clone + Send + Sync + 'static>() {}
+    assert_shareable::<LwsRpcClient>();
+
+    let (addr, requests) = mock_server(200, include_str!("fixtures/login.json").to_string()).await;
+    let (address, view_key) = credentials();
+    let client = LwsRpcClient::new(addr, None);
+
+    let tasks: Vec<_> = (0..8)
+        .map(|_| {
+            let client = client.clone();
+            tokio::spawn(async move { client.login(address, view_key, false, false).await })
+        })
+        .collect();
+    for task in tasks {
+        assert_eq!(task.await.unwrap().unwrap().start_height, Some(2670000));
+    }
+    assert_eq!(requests.lock().unwrap().len(), 8);
+}
+
+fn unspent_outs_route(path: &str) -> (u16, String) {
+    match path {
+        "/get_unspent_outs" => {
+            let mut outs: Value =
+                serde_json::from_str(include_str!("fixtures/get_unspent_outs.json")).unwrap();
+            let mut small = outs["outputs"][0].clone();
+            small["amount"] = json!("1000000000000");
+            small["index"] = json!(1);
+            small["global_index"] = json!(61254840);
+            outs["outputs"].as_array_mut().unwrap().insert(0, small);
+            (200, outs.to_string())
+        }
+        "/get_random_outs" => (
+            200,
+            include_str!("fixtures/get_random_outs.json").to_string(),
+        ),
+        // the outputs above have 20 confirmations
+        "/get_address_info" => {
+            let mut info: Value =
+                serde_json::from_str(include_str!("fixtures/get_address_info.json")).unwrap();
+            info["blockchain_height"] = json!(2676360);
+            (200, info.to_string())
+        }
+        _ => (404, String::new()),
+    }
+}
+
+#[tokio::test]
+async fn test_prepare_spend() {
+    let (addr, requests) = mock_router(unspent_outs_route).await;
+    let (address, view_key) = credentials();
+    let client = LwsRpcClient::new(addr, None);
+    let account = client.account(address, view_key);
+
+    let context = client
+        .prepare_spend(
+            &account,
+            monero::Amount::from_pico(2_000_000_000_000),
+            16,
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.outputs.len(), 1);
+    assert_eq!(context.outputs[0].amount, "15000000000000");
+    assert_eq!(context.decoys.amount_outs[0].outputs.len(), 16);
+    assert_eq!(context.per_kb_fee, 20000);
+
+    let requests = requests.lock().unwrap();
+    assert!(requests[0].contains("\"mixin\":15"));
+    assert!(requests[1].starts_with("POST /get_address_info "));
+    assert!(requests[2].contains("\"count\":16"));
+    assert!(requests[2].contains("\"amounts\":[\"0\"]"));
+}
+
+#[tokio::test]
+async fn test_prepare_spend_skips_unspendable_outputs() {
+    let (address, view_key) = credentials();
+    let key_image = KeyImage::from_str(VIEW_KEY).unwrap();
+    // the largest output is locked, then also spent by the wallet
+    for (field, value, spent_key_images) in [
+        ("unlock_time", json!(2676500), vec![]),
+        ("coinbase", json!(true), vec![]),
+        ("spend_key_images", json!([VIEW_KEY]), vec![key_image]),
+    ] {
+        let (addr, _) = mock_router(move |path| {
+            let (status, body) = unspent_outs_route(path);
+            if path != "/get_unspent_outs" {
+                return (status, body);
+            }
+            let mut outs: Value = serde_json::from_str(&body).unwrap();
+            outs["outputs"][1][field] = value.clone();
+            (status, outs.to_string())
+        })
+        .await;
+        let client = LwsRpcClient::new(addr, None);
+        let account = client.account(address, view_key);
+
+        let context = client
+            .prepare_spend(
+                &account,
+                monero::Amount::from_pico(500_000_000_000),
+                16,
+                &spent_key_images,
+            )
+            .await
+            .unwrap();
+        assert_eq!(context.outputs.len(), 1);
+        assert_eq!(context.outputs[0].amount, "1000000000000");
+
+        let err = client
+            .prepare_spend(
+                &account,
+                monero::Amount::from_pico(2_000_000_000_000),
+                16,
+                &spent_key_images,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<LwsError>(),
+            Some(LwsError::InsufficientFunds { available, .. }) if *available == 1_000_000_000_000
+        ));
+    }
+}
+
+#[tokio::test]
+async fn test_prepare_spend_keeps_possibly_spent_outputs() {
+    let (address, view_key) = credentials();
+    // the largest output was used in a ring, not necessarily spent
+    let (addr, _) = mock_router(|path| {
+        let (status, body) = unspent_outs_route(path);
+        if path != "/get_unspent_outs" {
+            return (status, body);
+        }
+        let mut outs: Value = serde_json::from_str(&body).unwrap();
+        outs["outputs"][1]["spend_key_images"] = json!([VIEW_KEY]);
+        (status, outs.to_string())
+    })
+    .await;
+    let client = LwsRpcClient::new(addr, None);
+    let account = client.account(address, view_key);
+
+    let context = client
+        .prepare_spend(
+            &account,
+            monero::Amount::from_pico(2_000_000_000_000),
+            16,
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(context.outputs.len(), 1);
+    assert_eq!(context.outputs[0].amount, "15000000000000");
+}
+
+#[tokio::test]
+async fn test_prepare_spend_insufficient_funds() {
+    let (addr, _) = mock_router(unspent_outs_route).await;
+    let (address, view_key) = credentials();
+    let client = LwsRpcClient::new(addr, None);
+    let account = client.account(address, view_key);
+
+    let err = client
+        .prepare_spend(
+            &account,
+            monero::Amount::from_pico(20_000_000_000_000),
+            16,
+            &[],
+        )
+        .await
+        .unwrap_err();
+    match err.downcast_ref::<LwsError>() {
+        Some(LwsError::InsufficientFunds {
+            required,
+            available,
+        }) => {
+            // the amount and the fee of spending both outputs
+            assert_eq!(*required, 20_000_044_300_000);
+            assert_eq!(*available, 16_000_000_000_000);
+        }
+        _ => panic!("expected insufficient funds, got {:?}", err),
+    }
+}
+
+#[tokio::test]
+async fn test_omitted_optional_params() {
+    let (addr, requests) = mock_server(
+        200,
+        include_str!("fixtures/import_wallet_request.json").to_string(),
+    )
+    .await;
+    let (address, view_key) = credentials();
+    let client = LwsRpcClient::new(addr, None);
+
+    client
+        .import_request(address, view_key, None)
+        .await
+        .unwrap();
+    client
+        .import_request(address, view_key, Some(2670000))
+        .await
+        .unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert!(!requests[0].contains("from_height"));
+    assert!(requests[1].contains("\"from_height\":2670000"));
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn test_call_raw() {
+    let (addr, requests) = mock_router(|path| match path {
+        "/get_version" => (
+            200,
+            r#"{"server_type":"monero-lws","api":65536}"#.to_string(),
+        ),
+        "/broken" => (
+            200,
+            include_str!("fixtures/error_bad_view_key.json").to_string(),
+        ),
+        _ => (404, String::new()),
+    })
+    .await;
+    let client = LwsRpcClient::new(addr, None);
+
+    let version = client
+        .call_raw("/get_version", json!({ "probe": true }))
+        .await
+        .unwrap();
+    assert_eq!(version["server_type"], json!("monero-lws"));
+    assert!(requests.lock().unwrap()[0].ends_with(r#"{"probe":true}"#));
+
+    let err = client.call_raw("broken", json!({})).await.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<LwsError>(),
+        Some(LwsError::Server { .. })
+    ));
+}
+
+/// Transport answering from the fixtures, without any socket, and recording the requests.
+#[derive(Clone, Debug, Default)]
+struct FixtureTransport {
+    requests: Arc<Mutex<Vec<HttpRequest>>>,
+}
+
+impl HttpTransport for FixtureTransport {
+    fn post(&self, request: HttpRequest) -> TransportFuture<'_> {
+        self.requests.lock().unwrap().push(request.clone());
+        Box::pin(async move {
+            let (status, body) = match request.url.rsplit('/').next() {
+                Some("login") => (StatusCode::OK, include_str!("fixtures/login.json")),
+                Some("submit_raw_tx") => (StatusCode::OK, r#"{"status":"OK"}"#),
+                // well-formed, but not an `AddressTxs`
+                Some("get_address_txs") => (
+                    StatusCode::OK,
+                    r#"{"total_received":"0","scanned_height":0,"scanned_block_height":true,"start_height":0,"blockchain_height":0}"#,
+                ),
+                // the account of `ADDRESS` is unknown, any other one is funded
+                Some("get_address_info")
+                    if !String::from_utf8_lossy(&request.body).contains(ADDRESS) =>
+                {
+                    (
+                        StatusCode::OK,
+                        include_str!("fixtures/get_address_info.json"),
+                    )
+                }
+                _ => (StatusCode::FORBIDDEN, ""),
+            };
+            let mut headers = HeaderMap::new();
+            headers.insert("server", "fixture/1.0".parse().unwrap());
+            Ok(HttpResponse {
+                status,
+                headers,
+                body: body.as_bytes().to_vec(),
+            })
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_custom_transport() {
+    let (address, view_key) = credentials();
+    let client = LwsRpcClientBuilder::new()
+        .transport(FixtureTransport::default())
+        .build("http://lws.invalid")
+        .unwrap();
+
+    let login = client.login(address, view_key, true, false).await.unwrap();
+    assert!(login.new_address);
+
+    let err = client
+        .get_address_info(address, view_key)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<LwsError>(),
+        Some(LwsError::HttpStatus { status, .. }) if *status == StatusCode::FORBIDDEN
+    ));
+    assert_eq!(
+        err.to_string(),
+        "HTTP status client error (403 Forbidden) for url (http://lws.invalid/get_address_info)"
+    );
+}
+
+#[tokio::test]
+async fn test_request_headers() {
+    let (address, view_key) = credentials();
+    let transport = FixtureTransport::default();
+    let client = LwsRpcClientBuilder::new()
+        .transport(transport.clone())
+        .build("http://lws.invalid")
+        .unwrap();
+    client.login(address, view_key, true, false).await.unwrap();
+
+    let requests = transport.requests.lock().unwrap();
+    assert_eq!(requests[0].headers["content-type"], "application/json");
+    assert_eq!(requests[0].headers["accept"], "application/json");
+}
+
+#[tokio::test]
+async fn test_user_agent() {
+    let (address, view_key) = credentials();
+    let transport = FixtureTransport::default();
+    let client = LwsRpcClientBuilder::new()
+        .transport(transport.clone())
+        .build("http://lws.invalid")
+        .unwrap();
+    client.login(address, view_key, true, false).await.unwrap();
+
+    let client = LwsRpcClientBuilder::new()
+        .transport(transport.clone())
+        .user_agent("wallet/1.2")
+        .build("http://lws.invalid")
+        .unwrap();
+    client.login(address, view_key, true, false).await.unwrap();
+
+    let requests = transport.requests.lock().unwrap();
+    assert_eq!(
+        requests[0].headers["user-agent"],
+        concat!("monero-lws-rs/", env!("CARGO_PKG_VERSION"))
+    );
+    assert_eq!(requests[1].headers["user-agent"], "wallet/1.2");
+}
+
+#[tokio::test]
+async fn test_submit_raw_tx() {
+    let raw_tx = include_str!("fixtures/raw_tx.hex").trim();
+    let tx: monero::Transaction =
+        monero::consensus::encode::deserialize(&hex::decode(raw_tx).unwrap()).unwrap();
+    let transport = FixtureTransport::default();
+    let client = LwsRpcClientBuilder::new()
+        .transport(transport.clone())
+        .build("http://lws.invalid")
+        .unwrap();
+
+    let rsp = client.submit_raw_tx(&tx).await.unwrap();
+    assert_eq!(rsp.status, "OK");
+
+    let requests = transport.requests.lock().unwrap();
+    let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(body, json!({ "tx": raw_tx }));
+}
+
+#[tokio::test]
+async fn test_get_address_txs_arc() {
+    let (addr, _) = mock_server(
+        200,
+        include_str!("fixtures/get_address_txs.json").to_string(),
+    )
+    .await;
+    let (address, view_key) = credentials();
+    let client = LwsRpcClient::new(addr, None);
+
+    let txs = client.get_address_txs_arc(address, view_key).await.unwrap();
+    let shared = txs.clone();
+    assert!(Arc::ptr_eq(&txs, &shared));
+    assert_eq!(shared.transactions.len(), 3);
+}
+
+#[tokio::test]
+async fn test_aggregate_balance() {
+    let (address, view_key) = credentials();
+    let client = LwsRpcClientBuilder::new()
+        .transport(FixtureTransport::default())
+        .build("http://lws.invalid")
+        .unwrap();
+    let public_key = monero::PublicKey::from_private_key(&view_key);
+    let funded = monero::Address::standard(monero::Network::Mainnet, public_key, public_key);
+    let accounts = [
+        client.account(funded, view_key),
+        client.account(address, view_key),
+        client.account(funded, view_key),
+    ];
+
+    let aggregate = client.aggregate_balance(&accounts).await;
+    assert_eq!(aggregate.balances.ok.len(), 2);
+    assert_eq!(aggregate.balances.ok[0].0, funded);
+    assert_eq!(aggregate.balances.errors.len(), 1);
+    assert_eq!(aggregate.balances.errors[0].0, address);
+    assert_eq!(aggregate.total.total_received, 70_000_000_000_000);
+    assert_eq!(aggregate.total.net, 46_000_000_000_000);
+}
+
+#[tokio::test]
+async fn test_get_address_info_batch() {
+    let (address, view_key) = credentials();
+    let client = LwsRpcClientBuilder::new()
+        .transport(FixtureTransport::default())
+        .build("http://lws.invalid")
+        .unwrap();
+    let public_key = monero::PublicKey::from_private_key(&view_key);
+    let funded = monero::Address::standard(monero::Network::Mainnet, public_key, public_key);
+
+    let batch = client
+        .get_address_info_batch(&[
+            client.account(address, view_key),
+            client.account(funded, view_key),
+        ])
+        .await
+        .into_result()
+        .unwrap();
+    assert_eq!(batch.ok.len(), 1);
+    assert_eq!(batch.ok[0].0, funded);
+    assert_eq!(batch.errors.len(), 1);
+    assert_eq!(batch.errors[0].0, address);
+
+    let err = client
+        .get_address_info_batch(&[client.account(address, view_key)])
+        .await
+        .into_result()
+        .unwrap_err();
+    assert!(err.downcast_ref::<LwsError>().is_some());
+    assert!(client
+        .get_address_info_batch(&[])
+        .await
+        .into_result()
+        .is_ok());
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn test_metrics() {
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    metrics::with_local_recorder(&recorder, || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let (address, view_key) = credentials();
+            let client = LwsRpcClientBuilder::new()
+                .transport(FixtureTransport::default())
+                .build("http://lws.invalid")
+                .unwrap();
+            client.login(address, view_key, true, false).await.unwrap();
+            client.login(address, view_key, true, false).await.unwrap();
+            client
+                .get_address_info(address, view_key)
+                .await
+                .unwrap_err();
+            client.get_address_txs(address, view_key).await.unwrap_err();
+        })
+    });
+
+    let snapshot = snapshotter.snapshot().into_vec();
+    let value = |name: &str, labels: &[(&str, &str)]| {
+        snapshot
+            .iter()
+            .find(|(key, ..)| {
+                let key = key.key();
+                key.name() == name
+                    && key
+                        .labels()
+                        .map(|label| (label.key(), label.value()))
+                        .eq(labels.iter().copied())
+            })
+            .map(|(.., value)| value)
+    };
+    let login = [("endpoint", "login")];
+    let info = [("endpoint", "get_address_info")];
+    assert_eq!(
+        value("lws_requests_total", &login),
+        Some(&DebugValue::Counter(2))
+    );
+    assert_eq!(
+        value("lws_requests_succeeded_total", &login),
+        Some(&DebugValue::Counter(2))
+    );
+    assert_eq!(
+        value(
+            "lws_requests_failed_total",
+            &[("endpoint", "get_address_info"), ("kind", "http_status")]
+        ),
+        Some(&DebugValue::Counter(1))
+    );
+    assert_eq!(value("lws_requests_succeeded_total", &info), None);
+    assert!(matches!(
+        value("lws_request_duration_seconds", &info),
+        Some(DebugValue::Histogram(samples)) if samples.len() == 1
+    ));
+
+    // a response failing to parse into its model is a failure
+    let txs = [("endpoint", "get_address_txs")];
+    assert_eq!(
+        value("lws_requests_total", &txs),
+        Some(&DebugValue::Counter(1))
+    );
+    assert_eq!(value("lws_requests_succeeded_total", &txs), None);
+    assert_eq!(
+        value(
+            "lws_requests_failed_total",
+            &[("endpoint", "get_address_txs"), ("kind", "deserialize")]
+        ),
+        Some(&DebugValue::Counter(1))
+    );
+    #[cfg(feature = "error-paths")]
+    assert_eq!(
+        value(
+            "lws_deserialize_failures_total",
+            &[
+                ("endpoint", "get_address_txs"),
+                ("field", "scanned_block_height")
+            ]
+        ),
+        Some(&DebugValue::Counter(1))
+    );
+}
+
+#[cfg(all(feature = "metrics", feature = "error-paths"))]
+#[test]
+fn test_metrics_field_label() {
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    metrics::with_local_recorder(&recorder, || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            // the first response has an invalid output at index 0, the second at index 1
+            let calls = Arc::new(AtomicUsize::new(0));
+            let (addr, _) = mock_router(move |path| {
+                let (status, body) = unspent_outs_route(path);
+                let mut outs: Value = serde_json::from_str(&body).unwrap();
+                let index = calls.fetch_add(1, Ordering::SeqCst);
+                outs["outputs"][index]["global_index"] = json!("invalid");
+                (status, outs.to_string())
+            })
+            .await;
+            let (address, view_key) = credentials();
+            let client = LwsRpcClient::new(addr, None);
+            for _ in 0..2 {
+                client
+                    .get_unspent_outs(
+                        address,
+                        view_key,
+                        monero::Amount::from_pico(0),
+                        15,
+                        true,
+                        monero::Amount::from_pico(0),
+                    )
+                    .await
+                    .unwrap_err();
+            }
+        })
+    });
+
+    let snapshot = snapshotter.snapshot().into_vec();
+    let fields: Vec<_> = snapshot
+        .iter()
+        .filter(|(key, ..)| key.key().name() == "lws_deserialize_failures_total")
+        .map(|(key, _, _, value)| {
+            let field = key
+                .key()
+                .labels()
+                .find(|label| label.key() == "field")
+                .map(|label| label.value().to_string());
+            (field, value)
+        })
+        .collect();
+    assert_eq!(
+        fields,
+        vec![(
+            Some("outputs[].global_index".to_string()),
+            &DebugValue::Counter(2)
+        )]
+    );
+}
+
+/// Answers `get_address_info` with an account that is 10 blocks behind for the first `lagging`
+/// requests, and fully scanned afterwards.
+fn catching_up_route(lagging: usize) -> impl Fn(&str) -> (u16, String) {
+    let polls = AtomicUsize::new(0);
+    move |_| {
+        let mut info: Value =
+            serde_json::from_str(include_str!("fixtures/get_address_info.json")).unwrap();
+        if polls.fetch_add(1, Ordering::SeqCst) < lagging {
+            info["scanned_block_height"] = json!(2676338);
+        }
+        (200, info.to_string())
+    }
+}
+
+#[tokio::test]
+async fn test_wait_until_scanned() {
+    let (address, view_key) = credentials();
+    let (addr, requests) = mock_router(catching_up_route(2)).await;
+    let client = LwsRpcClient::new(addr, None);
+    let account = client.account(address, view_key);
+    let info = client
+        .wait_until_scanned(
+            &account,
+            Duration::from_millis(10),
+            Duration::from_secs(5),
+            &CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(info.scanned_block_height, 2676348);
+    assert_eq!(requests.lock().unwrap().len(), 3);
+
+    let (addr, _) = mock_router(catching_up_route(usize::MAX)).await;
+    let client = LwsRpcClient::new(addr, None);
+    let err = client
+        .wait_until_scanned(
+            &account,
+            Duration::from_millis(10),
+            Duration::from_millis(100),
+            &CancellationToken::new(),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<LwsError>(),
+        Some(LwsError::Timeout { timeout }) if *timeout == Duration::from_millis(100)
+    ));
+
+    let cancel = CancellationToken::new();
+    tokio::spawn({
+        let cancel = cancel.clone();
+        async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            cancel.cancel();
+        }
+    });
+    let err = client
+        .wait_until_scanned(
+            &account,
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+            &cancel,
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<LwsError>(),
+        Some(LwsError::Cancelled)
+    ));
+}
+
+#[tokio::test]
+async fn test_wait_until_scanned_connection_reset() {
+    let (address, view_key) = credentials();
+    // the first two polls have their connection reset
+    let resets = || {
+        let polls = AtomicUsize::new(0);
+        move |_: &str| {
+            if polls.fetch_add(1, Ordering::SeqCst) < 2 {
+                (0, String::new())
+            } else {
+                (
+                    200,
+                    include_str!("fixtures/get_address_info.json").to_string(),
+                )
+            }
+        }
+    };
+    let (addr, requests) = mock_router(resets()).await;
+    let client = LwsRpcClient::new(addr, None);
+    let account = client.account(address, view_key);
+    client
+        .wait_until_scanned(
+            &account,
+            Duration::from_millis(10),
+            Duration::from_secs(5),
+            &CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(requests.lock().unwrap().len(), 3);
+
+    let (addr, requests) = mock_router(resets()).await;
+    let client = LwsRpcClientBuilder::new()
+        .poll_retries(1)
+        .build(addr)
+        .unwrap();
+    let err = client
+        .wait_until_scanned(
+            &account,
+            Duration::from_millis(10),
+            Duration::from_secs(5),
+            &CancellationToken::new(),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<LwsError>(),
+        Some(LwsError::Http { .. })
+    ));
+    assert_eq!(requests.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_network_mismatch() {
+    let (addr, _) = mock_server(
+        200,
+        include_str!("fixtures/import_wallet_request.json").to_string(),
+    )
+    .await;
+    let (address, view_key) = credentials();
+
+    for network in [None, Some(monero::Network::Mainnet)] {
+        let mut builder = LwsRpcClientBuilder::new();
+        if let Some(network) = network {
+            builder = builder.network(network);
+        }
+        let client = builder.build(addr.clone()).unwrap();
+        client
+            .import_request(address, view_key, None)
+            .await
+            .unwrap();
+    }
+    let client = LwsRpcClientBuilder::new()
+        .network_of(&address)
+        .build(addr.clone())
+        .unwrap();
+    client
+        .import_request(address, view_key, None)
+        .await
+        .unwrap();
+
+    let client = LwsRpcClientBuilder::new()
+        .network(monero::Network::Stagenet)
+        .build(addr)
+        .unwrap();
+    let err = client
+        .import_request(address, view_key, None)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<LwsError>(),
+        Some(LwsError::NetworkMismatch {
+            expected: monero::Network::Stagenet,
+            found: monero::Network::Mainnet,
+        })
+    ));
+}
+
+#[tokio::test]
+async fn test_call_with_meta() {
+    let client = LwsRpcClientBuilder::new()
+        .transport(FixtureTransport::default())
+        .build("http://lws.invalid")
+        .unwrap();
+    let (login, meta) = client
+        .call_with_meta::<LoginResponse>("login", json!({ "address": ADDRESS }))
+        .await
+        .unwrap();
+    assert!(login.new_address);
+    assert_eq!(meta.status, StatusCode::OK);
+    assert_eq!(meta.server.as_deref(), Some("fixture/1.0"));
+    assert_eq!(meta.retry_after, None);
+}
+
+#[tokio::test]
+async fn test_error_source() {
+    use std::error::Error;
+
+    let (address, view_key) = credentials();
+    // nothing listens on the port once the listener is dropped
+    let addr = {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    };
+    let err = LwsRpcClient::new(addr, None)
+        .get_address_info(address, view_key)
+        .await
+        .unwrap_err();
+    let err = err.downcast_ref::<LwsError>().unwrap();
+    assert!(matches!(err, LwsError::Http { .. }));
+    assert_eq!(err.to_string(), "HTTP request failed");
+    assert!(err.source().unwrap().is::<reqwest::Error>());
+
+    let (addr, _) = mock_server(200, "<html>".to_string()).await;
+    let err = LwsRpcClient::new(addr, None)
+        .get_address_info(address, view_key)
+        .await
+        .unwrap_err();
+    let err = err.downcast_ref::<LwsError>().unwrap();
+    assert!(matches!(err, LwsError::Deserialize { .. }));
+    assert_eq!(err.to_string(), "invalid response");
+    assert!(err.source().unwrap().is::<serde_json::Error>());
+}
+
+#[tokio::test]
+async fn test_connection_pool_options() {
+    let (addr, requests) = mock_server(200, include_str!("fixtures/login.json").to_string()).await;
+    let (address, view_key) = credentials();
+    let client = LwsRpcClientBuilder::new()
+        .pool_max_idle_per_host(0)
+        .pool_idle_timeout(Duration::from_secs(1))
+        .build(addr)
+        .unwrap();
+    for _ in 0..2 {
+        client.login(address, view_key, false, false).await.unwrap();
+    }
+    assert_eq!(requests.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_submit_and_confirm() {
+    let raw_tx = hex::decode(include_str!("fixtures/raw_tx.hex").trim()).unwrap();
+    let tx: monero::Transaction = monero::consensus::encode::deserialize(&raw_tx).unwrap();
+    let hash = monero::cryptonote::hash::Hashable::hash(&tx);
+    let polls = AtomicUsize::new(0);
+    let (addr, requests) = mock_router(move |path| match path {
+        "/submit_raw_tx" => (200, r#"{"status":"OK"}"#.to_string()),
+        _ => {
+            let mut txs: Value =
+                serde_json::from_str(include_str!("fixtures/get_address_txs.json")).unwrap();
+            // the transaction reaches the mempool after two polls
+            if polls.fetch_add(1, Ordering::SeqCst) >= 2 {
+                txs["transactions"][2]["hash"] = json!(hex::encode(hash.as_bytes()));
+            }
+            (200, txs.to_string())
+        }
+    })
+    .await;
+    let (address, view_key) = credentials();
+    let client = LwsRpcClient::new(addr, None);
+    let account = client.account(address, view_key);
+
+    let confirmed = client
+        .submit_and_confirm(
+            &tx,
+            &account,
+            Duration::from_millis(10),
+            Duration::from_secs(5),
+            &CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(confirmed, hash);
+    assert_eq!(requests.lock().unwrap().len(), 4);
+
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let err = client
+        .submit_and_confirm(
+            &tx,
+            &account,
+            Duration::from_millis(10),
+            Duration::from_secs(5),
+            &cancel,
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<LwsError>(),
+        Some(LwsError::Cancelled)
+    ));
+    assert_eq!(requests.lock().unwrap().len(), 4);
+}
+
+#[cfg(feature = "compression")]
+#[tokio::test]
+async fn test_compress_requests() {
+    use std::io::Read;
+
+    let (address, view_key) = credentials();
+    let transport = FixtureTransport::default();
+    for compress in [false, true] {
+        let client = LwsRpcClientBuilder::new()
+            .transport(transport.clone())
+            .compress_requests(compress)
+            .build("http://lws.invalid")
+            .unwrap();
+        client.login(address, view_key, true, false).await.unwrap();
+    }
+
+    let requests = transport.requests.lock().unwrap();
+    assert!(requests[0].headers.get("content-encoding").is_none());
+    assert_eq!(requests[1].headers["content-encoding"], "gzip");
+    let mut body = Vec::new();
+    flate2::read::GzDecoder::new(&requests[1].body[..])
+        .read_to_end(&mut body)
+        .unwrap();
+    assert_eq!(body, requests[0].body);
+}
+
+#[tokio::test]
+async fn test_account_not_found() {
+    let (address, view_key) = credentials();
+    let (addr, _) = mock_server(
+        404,
+        include_str!("fixtures/error_account_not_found.json").to_string(),
+    )
+    .await;
+    let err = LwsRpcClient::new(addr, None)
+        .get_address_info(address, view_key)
+        .await
+        .unwrap_err();
+    match err.downcast_ref::<LwsError>() {
+        Some(LwsError::AccountNotFound { reason }) => {
+            assert_eq!(reason, "account does not exist")
+        }
+        _ => panic!("expected account not found, got {:?}", err),
+    }
+
+    // other failures are still reported as is
+    let (addr, _) = mock_server(
+        403,
+        include_str!("fixtures/error_bad_view_key.json").to_string(),
+    )
+    .await;
+    let err = LwsRpcClient::new(addr, None)
+        .get_address_info(address, view_key)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<LwsError>(),
+        Some(LwsError::Server { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_account_refresh() {
+    let (addr, requests) = mock_router(|path| match path {
+        "/get_address_info" => (
+            200,
+            include_str!("fixtures/get_address_info.json").to_string(),
+        ),
+        _ => (
+            200,
+            include_str!("fixtures/get_address_txs.json").to_string(),
+        ),
+    })
+    .await;
+    let (address, view_key) = credentials();
+    let account = LwsRpcClient::new(addr, None).account(address, view_key);
+
+    let refresh = account.refresh(2676340).await.unwrap();
+    assert_eq!(refresh.info.scanned_block_height, 2676348);
+    let heights: Vec<_> = refresh.new_txs.iter().map(|tx| tx.height).collect();
+    assert_eq!(heights, [Some(2676340), None]);
+    assert_eq!(requests.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_get_random_outs_for_ring_size() {
+    let (addr, requests) = mock_server(
+        200,
+        include_str!("fixtures/get_random_outs.json").to_string(),
+    )
+    .await;
+    let client = LwsRpcClient::new(addr, None);
+    let amounts = vec![monero::Amount::from_pico(0)];
+
+    client
+        .get_random_outs_for_ring_size(16, amounts.clone())
+        .await
+        .unwrap();
+    assert!(client
+        .get_random_outs_for_ring_size(1, amounts)
+        .await
+        .is_err());
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].contains("\"count\":15"));
+}
+
+#[tokio::test]
+async fn test_path_prefix() {
+    let (address, view_key) = credentials();
+    let transport = FixtureTransport::default();
+    for (addr, prefix) in [
+        ("http://lws.invalid", "lws"),
+        ("http://lws.invalid/", "/lws/"),
+        ("http://lws.invalid", "/"),
+    ] {
+        let client = LwsRpcClientBuilder::new()
+            .transport(transport.clone())
+            .path_prefix(prefix)
+            .build(addr)
+            .unwrap();
+        client.login(address, view_key, true, false).await.unwrap();
+    }
+
+    let requests = transport.requests.lock().unwrap();
+    let urls: Vec<_> = requests
+        .iter()
+        .map(|request| request.url.as_str())
+        .collect();
+    assert_eq!(
+        urls,
+        [
+            "http://lws.invalid/lws/login",
+            "http://lws.invalid/lws/login",
+            "http://lws.invalid/login",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_sync_session() {
+    let info = catching_up_route(1);
+    let txs_polls = AtomicUsize::new(0);
+    let (addr, requests) = mock_router(move |path| match path {
+        "/login" => (200, include_str!("fixtures/login.json").to_string()),
+        "/import_wallet_request" => (
+            200,
+            include_str!("fixtures/import_wallet_request.json").to_string(),
+        ),
+        "/get_address_txs" => {
+            let mut txs: Value =
+                serde_json::from_str(include_str!("fixtures/get_address_txs.json")).unwrap();
+            // the mempool transaction is mined from the third poll
+            if txs_polls.fetch_add(1, Ordering::SeqCst) >= 2 {
+                txs["transactions"][2]["height"] = json!(2676349);
+                txs["transactions"][2]["mempool"] = json!(false);
+            }
+            (200, txs.to_string())
+        }
+        _ => info(path),
+    })
+    .await;
+    let (address, view_key) = credentials();
+    let client = LwsRpcClient::new(addr, None);
+    let mut session = SyncSession::new(client.account(address, view_key))
+        .generated_locally(true)
+        .import_from(2670000);
+    assert_eq!(session.state(), SyncState::NotLoggedIn);
+
+    let events = session.step().await.unwrap();
+    assert!(matches!(events[..], [SyncEvent::LoggedIn(_)]));
+    assert_eq!(session.state(), SyncState::Importing);
+
+    let events = session.step().await.unwrap();
+    assert!(matches!(events[..], [SyncEvent::ImportRequested(_)]));
+    assert_eq!(session.state(), SyncState::Scanning);
+
+    let events = session.step().await.unwrap();
+    assert_eq!(
+        events[0],
+        SyncEvent::Progress {
+            scanned_block_height: 2676338,
+            blockchain_height: 2676349,
+        }
+    );
+    assert!(matches!(&events[1], SyncEvent::NewTransactions(txs) if txs.len() == 3));
+    assert_eq!(events.len(), 2);
+    assert_eq!(session.state(), SyncState::Scanning);
+
+    let events = session.step().await.unwrap();
+    assert_eq!(
+        events,
+        [
+            SyncEvent::Progress {
+                scanned_block_height: 2676348,
+                blockchain_height: 2676349,
+            },
+            SyncEvent::Synced,
+        ]
+    );
+    assert_eq!(session.state(), SyncState::Synced);
+
+    let events = session.step().await.unwrap();
+    assert_eq!(events.len(), 2);
+    match &events[1] {
+        SyncEvent::Confirmed(txs) => {
+            assert_eq!(txs.len(), 1);
+            assert_eq!(txs[0].height, Some(2676349));
+        }
+        other => panic!("expected a confirmation, got {:?}", other),
+    }
+    assert_eq!(session.state(), SyncState::Synced);
+
+    let events = session.step().await.unwrap();
+    assert_eq!(events.len(), 1);
+
+    let requests = requests.lock().unwrap();
+    assert!(requests[0].contains("\"create_account\":true"));
+    assert!(requests[0].contains("\"generated_locally\":true"));
+    assert!(requests[1].contains("\"from_height\":2670000"));
+}
+
+#[cfg(feature = "signing")]
+#[tokio::test]
+async fn test_hmac_key() {
+    use hmac::Mac;
+
+    let (address, view_key) = credentials();
+    let transport = FixtureTransport::default();
+    let builders = [
+        LwsRpcClientBuilder::new(),
+        LwsRpcClientBuilder::new().hmac_key(b"secret"),
+        LwsRpcClientBuilder::new()
+            .hmac_key(b"secret")
+            .hmac_header("X-Signature"),
+    ];
+    for builder in builders {
+        let client = builder
+            .transport(transport.clone())
+            .build("http://lws.invalid")
+            .unwrap();
+        client.login(address, view_key, true, false).await.unwrap();
+    }
+
+    let requests = transport.requests.lock().unwrap();
+    let mut mac = hmac::Hmac::<sha2::Sha256>::new_from_slice(b"secret").unwrap();
+    mac.update(&requests[1].body);
+    let signature = hex::encode(mac.finalize().into_bytes());
+    assert!(requests[0].headers.get("x-lws-signature").is_none());
+    assert_eq!(requests[1].headers["x-lws-signature"], signature.as_str());
+    assert!(requests[2].headers.get("x-lws-signature").is_none());
+    assert_eq!(requests[2].headers["x-signature"], signature.as_str());
+}
+
+#[cfg(feature = "record")]
+#[tokio::test]
+async fn test_record_and_replay() {
+    use monero_lws::record::{Interaction, ReplayTransport, REDACTED};
+
+    let (address, view_key) = credentials();
+    let path = std::env::temp_dir().join(format!("monero-lws-{}.jsonl", uuid::Uuid::new_v4()));
+    let client = LwsRpcClientBuilder::new()
+        .transport(FixtureTransport::default())
+        .record_to(&path)
+        .build("http://lws.invalid")
+        .unwrap();
+    let login = client.login(address, view_key, true, false).await.unwrap();
+    assert!(client.get_address_info(address, view_key).await.is_err());
+
+    let replay = ReplayTransport::from_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let interactions: &[Interaction] = replay.interactions();
+    assert_eq!(interactions.len(), 2);
+    assert_eq!(interactions[0].path, "/login");
+    assert_eq!(interactions[1].status, 403);
+    for interaction in interactions {
+        let request: Value = serde_json::from_str(&interaction.request).unwrap();
+        assert_eq!(request["view_key"], REDACTED);
+        assert_eq!(request["address"], ADDRESS);
+    }
+
+    let client = LwsRpcClientBuilder::new()
+        .transport(replay)
+        .build("http://lws.invalid")
+        .unwrap();
+    assert_eq!(
+        client.login(address, view_key, true, false).await.unwrap(),
+        login
+    );
+    let err = client
+        .get_address_info(address, view_key)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<LwsError>(),
+        Some(LwsError::HttpStatus { status, .. }) if *status == StatusCode::FORBIDDEN
+    ));
+    let err = client
+        .login(address, view_key, false, false)
+        .await
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("no recorded response for /login"));
+}
+
+#[cfg(feature = "record")]
+#[tokio::test]
+async fn test_record_view_keys() {
+    let (address, view_key) = credentials();
+    let path = std::env::temp_dir().join(format!("monero-lws-{}.jsonl", uuid::Uuid::new_v4()));
+    let client = LwsRpcClientBuilder::new()
+        .transport(FixtureTransport::default())
+        .record_to(&path)
+        .record_view_keys(true)
+        .build("http://lws.invalid")
+        .unwrap();
+    client.login(address, view_key, true, false).await.unwrap();
+
+    let recording = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(recording.contains(VIEW_KEY));
+}
+
+#[tokio::test]
+async fn test_jsonrpc_envelope() {
+    let (address, view_key) = credentials();
+    let login: Value = serde_json::from_str(include_str!("fixtures/login.json")).unwrap();
+    let body = json!({ "jsonrpc": "2.0", "result": login, "id": 0 });
+    let (addr, _) = mock_server(200, body.to_string()).await;
+    let client = LwsRpcClientBuilder::new()
+        .jsonrpc(true)
+        .build(addr.clone())
+        .unwrap();
+    let response = client.login(address, view_key, true, false).await.unwrap();
+    assert_eq!(response.start_height, Some(2670000));
+
+    // the default is the flat shape
+    let err = LwsRpcClient::new(addr, None)
+        .login(address, view_key, true, false)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<LwsError>(),
+        Some(LwsError::Deserialize { .. })
+    ));
+
+    let body = json!({
+        "jsonrpc": "2.0",
+        "error": { "code": -32601, "message": "Method not found" },
+        "id": 0,
+    });
+    let (addr, _) = mock_server(200, body.to_string()).await;
+    let client = LwsRpcClientBuilder::new()
+        .jsonrpc(true)
+        .build(addr)
+        .unwrap();
+    let err = client
+        .login(address, view_key, true, false)
+        .await
+        .unwrap_err();
+    match err.downcast_ref::<LwsError>() {
+        Some(LwsError::Server { status, reason }) => {
+            assert_eq!(status, "-32601");
+            assert_eq!(reason.as_deref(), Some("Method not found"));
+        }
+        _ => panic!("expected a server error, got {:?}", err),
+    }
+}
@@ -1,6 +1,45 @@
-use serde_json::json;
+// The original login tests compare booleans with `assert_eq!`
+#![allow(clippy::bool_assert_comparison)]
 
-use monero_lws::LoginResponse;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    convert::TryFrom,
+    fmt::Debug,
+};
+
+use monero_lws::{
+    parse, select_decoys_gamma, validate_ring, AccountSnapshot, AddressInfo, AddressTxs,
+    AmountOuts, BalanceSummary, BlockHash, Clock, FeePriority, HashString, ImportResponse,
+    KeyImage, LoginOutcome, LoginResponse, LwsError, MoneroResult, PaymentIdField, ScanState,
+    ToJsonPretty, TxDirection, TxHash, UnspentOuts,
+};
+
+const ADDRESS_INFO: &str = include_str!("fixtures/get_address_info.json");
+const ADDRESS_TXS: &str = include_str!("fixtures/get_address_txs.json");
+const UNSPENT_OUTS: &str = include_str!("fixtures/get_unspent_outs.json");
+const IMPORT_RESPONSE: &str = include_str!("fixtures/import_wallet_request.json");
+const LOGIN_RESPONSE: &str = include_str!("fixtures/login.json");
+const RANDOM_OUTS: &str = include_str!("fixtures/get_random_outs.json");
+const RANDOM_OUTS_MULTI: &str = include_str!("fixtures/get_random_outs_multi.json");
+const ERROR_BAD_VIEW_KEY: &str = include_str!("fixtures/error_bad_view_key.json");
+const ERROR_UNKNOWN_ADDRESS: &str = include_str!("fixtures/error_unknown_address.json");
+
+/// Deserializes a fixture, serializes it back and checks that the result is both equal to the
+/// golden fixture and deserializes to the same model.
+fn round_trip<T>(fixture: &str) -> (T, Value)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let golden: Value = serde_json::from_str(fixture).unwrap();
+    let model: T = serde_json::from_value(golden.clone()).unwrap();
+    let serialized = serde_json::to_value(&model).unwrap();
+    assert_eq!(serialized, golden);
+    let again: T = serde_json::from_value(serialized.clone()).unwrap();
+    assert_eq!(again, model);
+    (model, serialized)
+}
 
 #[test]
 fn test_deserialize_boolean() {
@@ -13,6 +52,7 @@ fn test_deserialize_boolean() {
     assert_eq!(response.generated_locally, true);
 }
 
+#[cfg(not(feature = "strict-bools"))]
 #[test]
 fn test_deserialize_boolean_number() {
     let response_json = json!({
@@ -23,3 +63,1306 @@ fn test_deserialize_boolean_number() {
     assert_eq!(response.new_address, false);
     assert_eq!(response.generated_locally, true);
 }
+
+#[cfg(not(feature = "strict-bools"))]
+#[test]
+fn test_deserialize_boolean_string() {
+    for (quoted, expected) in [("0", false), ("1", true), ("false", false), ("true", true)] {
+        let response_json = json!({
+            "new_address": quoted,
+            "generated_locally": quoted,
+        });
+        let response: LoginResponse = serde_json::from_value(response_json).unwrap();
+        assert_eq!(response.new_address, expected);
+        assert_eq!(response.generated_locally, expected);
+
+        let mut import_json: Value = serde_json::from_str(IMPORT_RESPONSE).unwrap();
+        import_json["new_request"] = json!(quoted);
+        import_json["request_fulfilled"] = json!(quoted);
+        let response: ImportResponse = serde_json::from_value(import_json).unwrap();
+        assert_eq!(response.new_request, expected);
+        assert_eq!(response.request_fulfilled, expected);
+    }
+
+    let response_json = json!({
+        "new_address": "yes",
+        "generated_locally": true,
+    });
+    assert!(serde_json::from_value::<LoginResponse>(response_json).is_err());
+}
+
+#[cfg(feature = "strict-bools")]
+#[test]
+fn test_strict_booleans() {
+    for lenient in [json!(0), json!(1), json!("true")] {
+        let response_json = json!({
+            "new_address": lenient,
+            "generated_locally": true,
+        });
+        assert!(serde_json::from_value::<LoginResponse>(response_json).is_err());
+    }
+    let mut txs: Value = serde_json::from_str(ADDRESS_TXS).unwrap();
+    txs["transactions"][0]["mempool"] = json!(0);
+    assert!(serde_json::from_value::<AddressTxs>(txs).is_err());
+}
+
+#[test]
+fn test_round_trip_address_info() {
+    let (info, _) = round_trip::<AddressInfo>(ADDRESS_INFO);
+    assert_eq!(info.spent_outputs.len(), 1);
+    assert_eq!(info.rates.unwrap().AUD, Some(231.5));
+}
+
+#[test]
+fn test_round_trip_transaction() {
+    let (txs, serialized) = round_trip::<AddressTxs>(ADDRESS_TXS);
+    assert_eq!(txs.transactions.len(), 3);
+    assert_eq!(txs.transactions[2].height, None);
+    for tx in serialized["transactions"].as_array().unwrap() {
+        assert!(tx["coinbase"].is_boolean());
+        assert!(tx["mempool"].is_boolean());
+    }
+}
+
+#[test]
+fn test_transaction_payment_ids() {
+    let long = "15ec997f8ce06b209be790c61bacf0f223faca1be2c6bb3d4c36af4d676f0234";
+    let mut txs: Value = serde_json::from_str(ADDRESS_TXS).unwrap();
+    txs["transactions"][0]["payment_id"] = json!(long);
+    let txs: AddressTxs = serde_json::from_value(txs).unwrap();
+    let payment_id = txs.transactions[0].payment_id.unwrap();
+    assert!(!payment_id.is_short());
+    assert_eq!(payment_id.to_string(), long);
+    assert_eq!(
+        txs.transactions[1].payment_id,
+        Some(PaymentIdField::from_hex("78fa75840f25672d").unwrap())
+    );
+    assert_eq!(txs.transactions[2].payment_id, None);
+
+    let serialized = serde_json::to_value(&txs).unwrap();
+    assert_eq!(serialized["transactions"][0]["payment_id"], json!(long));
+
+    let mut txs: Value = serde_json::from_str(ADDRESS_TXS).unwrap();
+    txs["transactions"][0]["payment_id"] = json!("78fa7584");
+    assert!(serde_json::from_value::<AddressTxs>(txs).is_err());
+}
+
+#[test]
+fn test_round_trip_output() {
+    let (outs, _) = round_trip::<UnspentOuts>(UNSPENT_OUTS);
+    assert_eq!(outs.outputs.len(), 1);
+    assert_eq!(outs.outputs[0].global_index, 61254839);
+}
+
+#[test]
+fn test_hex_byte_fields() {
+    let (outs, serialized) = round_trip::<UnspentOuts>(UNSPENT_OUTS);
+    let output = &outs.outputs[0];
+    assert_eq!(output.rct.len(), 32);
+    assert_eq!(
+        output.rct_hex(),
+        "604f61f7feb69a84ba4dcb652f11aee5027e4040ac7c24365d6e271fe43c442f"
+    );
+    assert_eq!(output.tx_prefix_hash.len(), 32);
+    assert_eq!(
+        serialized["outputs"][0]["tx_prefix_hash"],
+        json!(output.tx_prefix_hash_hex())
+    );
+
+    // commitment, mask and amount of older servers, and pre-RingCT outputs
+    for rct in [&"ab".repeat(96), ""] {
+        let mut body = serialized.clone();
+        body["outputs"][0]["rct"] = json!(rct);
+        let outs: UnspentOuts = serde_json::from_value(body.clone()).unwrap();
+        assert_eq!(outs.outputs[0].rct.len(), rct.len() / 2);
+        assert_eq!(serde_json::to_value(&outs).unwrap(), body);
+    }
+
+    let mut body = serialized;
+    body["outputs"][0]["rct"] = json!("not hex");
+    assert!(serde_json::from_value::<UnspentOuts>(body).is_err());
+}
+
+#[test]
+fn test_round_trip_import_response() {
+    let (response, serialized) = round_trip::<ImportResponse>(IMPORT_RESPONSE);
+    assert!(response.new_request);
+    assert!(serialized["new_request"].is_boolean());
+    assert!(serialized["request_fulfilled"].is_boolean());
+}
+
+#[test]
+fn test_round_trip_login_response() {
+    let (response, serialized) = round_trip::<LoginResponse>(LOGIN_RESPONSE);
+    assert_eq!(response.start_height, Some(2670000));
+    assert!(serialized["new_address"].is_boolean());
+    assert!(serialized["generated_locally"].is_boolean());
+}
+
+#[cfg(not(feature = "strict-bools"))]
+#[test]
+fn test_round_trip_boolean_number_as_boolean() {
+    let mut golden: Value = serde_json::from_str(LOGIN_RESPONSE).unwrap();
+    golden["new_address"] = json!(1);
+    golden["generated_locally"] = json!(0);
+    let response: LoginResponse = serde_json::from_value(golden).unwrap();
+    let serialized = serde_json::to_value(&response).unwrap();
+    assert_eq!(serialized["new_address"], json!(true));
+    assert_eq!(serialized["generated_locally"], json!(false));
+}
+
+fn server_error_reason(fixture: &str) -> Option<String> {
+    let result: MoneroResult<AddressInfo> = serde_json::from_str(fixture).unwrap();
+    match result.into_inner() {
+        Err(LwsError::Server { reason, .. }) => reason,
+        other => panic!("expected a server error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_failed_status_bad_view_key() {
+    assert_eq!(
+        server_error_reason(ERROR_BAD_VIEW_KEY).as_deref(),
+        Some("Viewkey provided is incorrect")
+    );
+}
+
+#[test]
+fn test_failed_status_unknown_address() {
+    let result: MoneroResult<AddressInfo> = serde_json::from_str(ERROR_UNKNOWN_ADDRESS).unwrap();
+    match result.into_inner() {
+        Err(LwsError::AccountNotFound { reason }) => assert_eq!(reason, "Address not found"),
+        other => panic!("expected account not found, got {:?}", other),
+    }
+    assert!(matches!(
+        parse::parse_address_info(ERROR_UNKNOWN_ADDRESS.as_bytes()),
+        Err(LwsError::AccountNotFound { .. })
+    ));
+}
+
+#[test]
+fn test_failed_statuses_agree() {
+    for (status, failed) in [("Failed", true), ("error", true), ("failed", false)] {
+        let body = json!({ "status": status, "reason": "Viewkey provided is incorrect" });
+        let result = serde_json::from_value::<MoneroResult<AddressInfo>>(body.clone());
+        assert_eq!(matches!(result, Ok(MoneroResult::Failed(_))), failed);
+        let parsed = parse::parse_address_info(body.to_string().as_bytes());
+        assert_eq!(matches!(parsed, Err(LwsError::Server { .. })), failed);
+    }
+}
+
+#[test]
+fn test_ok_status() {
+    let mut body: Value = serde_json::from_str(LOGIN_RESPONSE).unwrap();
+    body["status"] = json!("OK");
+    let result: MoneroResult<LoginResponse> = serde_json::from_value(body).unwrap();
+    assert_eq!(
+        result.into_inner().unwrap(),
+        serde_json::from_str::<LoginResponse>(LOGIN_RESPONSE).unwrap()
+    );
+}
+
+#[test]
+fn test_hashes_as_map_keys() {
+    let txs: AddressTxs = serde_json::from_str(ADDRESS_TXS).unwrap();
+    let by_hash: HashMap<_, _> = txs
+        .transactions
+        .iter()
+        .map(|tx| (tx.hash.clone(), tx.id))
+        .collect();
+    assert_eq!(by_hash.len(), 3);
+    assert_eq!(by_hash[&txs.transactions[1].hash], 4903);
+
+    let ordered: BTreeMap<_, _> = txs
+        .transactions
+        .iter()
+        .map(|tx| (tx.hash.clone(), tx.id))
+        .collect();
+    let keys: Vec<_> = ordered.keys().map(|hash| hash.to_string()).collect();
+    let mut sorted = keys.clone();
+    sorted.sort();
+    assert_eq!(keys, sorted);
+
+    let blocks: BTreeSet<BlockHash> = [BlockHash::repeat_byte(2), BlockHash::repeat_byte(1)]
+        .iter()
+        .cloned()
+        .collect();
+    assert_eq!(blocks.iter().next(), Some(&BlockHash::repeat_byte(1)));
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_address_txs_to_csv() {
+    let txs: AddressTxs = serde_json::from_str(ADDRESS_TXS).unwrap();
+    let mut csv = Vec::new();
+    txs.to_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let lines: Vec<_> = csv.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "hash,height,timestamp,total_received,total_sent,coinbase,mempool,payment_id",
+            "8fe2eea7a083b3d2e51d4722595f3e6a1c43938e56403f9f9134a4c4c3c797b3,2671205,2022-07-20T09:14:02Z,20000000000000,0,false,false,",
+            "15ec997f8ce06b209be790c61bacf0f223faca1be2c6bb3d4c36af4d676f0234,2676340,2022-07-25T17:41:55Z,15000000000000,20000000000000,false,false,78fa75840f25672d",
+            "6d8caa3e0b3250afe6bc3f83e95e6015e41d462018f4d5b8b8f18eb8f36adb82,,2022-07-26T08:02:31Z,2000000000000,0,false,true,",
+        ]
+    );
+}
+
+fn unspent_outs_with_fee(per_kb_fee: Value) -> serde_json::Result<UnspentOuts> {
+    let mut body: Value = serde_json::from_str(UNSPENT_OUTS).unwrap();
+    body["per_kb_fee"] = per_kb_fee;
+    serde_json::from_value(body)
+}
+
+#[test]
+fn test_fee_for_priority() {
+    let mut outs: UnspentOuts = serde_json::from_str(UNSPENT_OUTS).unwrap();
+    assert!(outs.fee_tiers.is_empty());
+    assert_eq!(outs.fee_for_priority(FeePriority::Priority), 20000);
+
+    let mut body: Value = serde_json::from_str(UNSPENT_OUTS).unwrap();
+    body["fees"] = json!([20000, 80000, 320000, 4000000]);
+    outs = serde_json::from_value(body.clone()).unwrap();
+    assert_eq!(outs.fee_for_priority(FeePriority::Low), 20000);
+    assert_eq!(outs.fee_for_priority(FeePriority::default()), 80000);
+    assert_eq!(outs.fee_for_priority(FeePriority::Elevated), 320000);
+    assert_eq!(outs.fee_for_priority(FeePriority::Priority), 4000000);
+    assert_eq!(serde_json::to_value(&outs).unwrap(), body);
+
+    outs.fee_tiers.truncate(2);
+    assert_eq!(outs.fee_for_priority(FeePriority::Priority), 80000);
+}
+
+#[cfg(feature = "lenient-amounts")]
+#[test]
+fn test_lenient_float_amounts() {
+    assert_eq!(
+        unspent_outs_with_fee(json!(1000.0)).unwrap().per_kb_fee,
+        1000
+    );
+    assert!(unspent_outs_with_fee(json!(1000.5)).is_err());
+    assert!(unspent_outs_with_fee(json!(18446744073709551616.0)).is_err());
+    let body = UNSPENT_OUTS.replace("\"per_kb_fee\": 20000", "\"per_kb_fee\": 1e3");
+    let outs: UnspentOuts = serde_json::from_str(&body).unwrap();
+    assert_eq!(outs.per_kb_fee, 1000);
+}
+
+#[cfg(not(feature = "lenient-amounts"))]
+#[test]
+fn test_strict_float_amounts() {
+    assert!(unspent_outs_with_fee(json!(1000.0)).is_err());
+    assert!(unspent_outs_with_fee(json!(1000.5)).is_err());
+    assert_eq!(unspent_outs_with_fee(json!(1000)).unwrap().per_kb_fee, 1000);
+}
+
+#[test]
+fn test_detect_reorg() {
+    let previous: AddressInfo = serde_json::from_str(ADDRESS_INFO).unwrap();
+    let mut current = previous.clone();
+    assert_eq!(current.detect_reorg(&previous), None);
+    current.scanned_block_height += 10;
+    assert_eq!(current.detect_reorg(&previous), None);
+    current.scanned_block_height = previous.scanned_block_height - 3;
+    assert_eq!(current.detect_reorg(&previous), Some(2676346));
+}
+
+#[test]
+fn test_login_restore_height() {
+    let response: LoginResponse = serde_json::from_str(LOGIN_RESPONSE).unwrap();
+    assert_eq!(response.restore_height(), 2670000);
+    assert_eq!(response.honors_start_height(2670000), Some(true));
+    assert_eq!(response.honors_start_height(2600000), Some(false));
+
+    let response: LoginResponse = serde_json::from_value(json!({
+        "new_address": false,
+        "generated_locally": false,
+    }))
+    .unwrap();
+    assert_eq!(response.restore_height(), 0);
+    assert_eq!(response.honors_start_height(2600000), None);
+}
+
+#[test]
+fn test_login_outcome() {
+    let response: LoginResponse = serde_json::from_str(LOGIN_RESPONSE).unwrap();
+    assert_eq!(
+        LoginOutcome::from(&response),
+        LoginOutcome::NewAccount {
+            start_height: Some(2670000),
+            generated_locally: false,
+        }
+    );
+
+    let response: LoginResponse = serde_json::from_value(json!({
+        "new_address": false,
+        "generated_locally": true,
+    }))
+    .unwrap();
+    assert_eq!(
+        LoginOutcome::from(response),
+        LoginOutcome::ExistingAccount { start_height: None }
+    );
+}
+
+#[test]
+fn test_account_snapshot() {
+    let info: AddressInfo = serde_json::from_str(ADDRESS_INFO).unwrap();
+    let mut snapshot = AccountSnapshot::from(&info);
+    assert_eq!(snapshot.scanned_block_height, 2676348);
+    assert_eq!(snapshot.latest_tx_height, Some(2676340));
+    assert_eq!(snapshot.total_sent, info.total_sent);
+
+    let mut txs: AddressTxs = serde_json::from_str(ADDRESS_TXS).unwrap();
+    let unchanged = snapshot.clone();
+    snapshot.merge(&txs);
+    assert_eq!(snapshot, unchanged);
+
+    txs.scanned_block_height += 5;
+    txs.blockchain_height += 5;
+    txs.total_received = "40000000000000".to_string();
+    txs.transactions[2].height = Some(2676352);
+    snapshot.merge(&txs);
+    assert_eq!(snapshot.blockchain_height, 2676354);
+    assert_eq!(snapshot.latest_tx_height, Some(2676352));
+    // the totals stay those of the address info, at its height
+    assert_eq!(snapshot.scanned_block_height, 2676348);
+    assert_eq!(snapshot.total_received, info.total_received);
+    assert_eq!(snapshot.total_sent, info.total_sent);
+    assert_eq!(snapshot.locked_funds, info.locked_funds);
+
+    // an older response does not move the snapshot back
+    let updated = snapshot.clone();
+    snapshot.merge(&serde_json::from_str(ADDRESS_TXS).unwrap());
+    assert_eq!(snapshot, updated);
+
+    let serialized = serde_json::to_string(&snapshot).unwrap();
+    assert_eq!(
+        serde_json::from_str::<AccountSnapshot>(&serialized).unwrap(),
+        snapshot
+    );
+}
+
+#[test]
+fn test_latest_tx_height() {
+    let mut info: AddressInfo = serde_json::from_str(ADDRESS_INFO).unwrap();
+    assert_eq!(info.latest_tx_height(), Some(2676340));
+    info.transaction_height = 0;
+    assert_eq!(info.latest_tx_height(), None);
+}
+
+#[cfg(not(feature = "strict-bools"))]
+#[test]
+fn test_quoted_integers() {
+    let txs: AddressTxs = serde_json::from_str(include_str!(
+        "fixtures/get_address_txs_quoted_integers.json"
+    ))
+    .unwrap();
+    assert_eq!(txs.transactions[0].unlock_time, 0);
+    assert_eq!(txs.transactions[0].mixin, 15);
+
+    let serialized = serde_json::to_value(&txs).unwrap();
+    assert_eq!(serialized["transactions"][0]["unlock_time"], json!(0));
+
+    let mut body = serialized;
+    body["transactions"][0]["mixin"] = json!("fifteen");
+    assert!(serde_json::from_value::<AddressTxs>(body).is_err());
+}
+
+#[test]
+fn test_ring_without_real_index() {
+    let outs: AmountOuts = serde_json::from_str(RANDOM_OUTS).unwrap();
+    let decoys = &outs.amount_outs[0];
+    let colliding = decoys.outputs[3].global_index;
+
+    let all: Vec<_> = decoys.outputs.iter().collect();
+    match validate_ring(colliding, &all) {
+        Err(LwsError::DuplicateRingMember { global_index }) => assert_eq!(global_index, colliding),
+        other => panic!("expected a duplicate ring member, got {:?}", other),
+    }
+
+    let filtered = decoys.without_index(colliding);
+    assert_eq!(filtered.len(), decoys.outputs.len() - 1);
+    validate_ring(colliding, &filtered).unwrap();
+
+    let repeated = vec![filtered[0], filtered[1], filtered[0]];
+    assert!(validate_ring(colliding, &repeated).is_err());
+}
+
+#[test]
+fn test_scan_state() {
+    let address = "888tNkZrPN6JsEgekjMnABU4TBzc2Dt29EPAvkRxbANsAnjyPbb3iQ1YBRk1UXcdRsiKc9dhwMVgN5S9cQUiyoogDavup3H";
+    let address: monero::Address = address.parse().unwrap();
+    let info: AddressInfo = serde_json::from_str(ADDRESS_INFO).unwrap();
+    let txs: AddressTxs = serde_json::from_str(ADDRESS_TXS).unwrap();
+
+    let state = ScanState::from_info(address, &info);
+    assert_eq!(state.last_height, 2676348);
+    assert_eq!(state, ScanState::from_txs(address, &txs));
+
+    let saved = serde_json::to_string(&state).unwrap();
+    assert_eq!(serde_json::from_str::<ScanState>(&saved).unwrap(), state);
+}
+
+#[test]
+fn test_unspent_outs_excluding() {
+    let mut outs: UnspentOuts = serde_json::from_str(UNSPENT_OUTS).unwrap();
+    let mut other = outs.outputs[0].clone();
+    other.global_index = 61254840;
+    outs.outputs.push(other);
+
+    assert_eq!(outs.excluding(&HashSet::new()).len(), 2);
+    let remaining = outs.excluding(&[61254839].iter().cloned().collect());
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].global_index, 61254840);
+}
+
+#[test]
+fn test_unspent_outs_sorted() {
+    let mut outs: UnspentOuts = serde_json::from_str(UNSPENT_OUTS).unwrap();
+    let first = outs.outputs[0].clone();
+    for (global_index, amount, height) in [
+        (61254838, "20000000000000", 2676345),
+        (61254837, "15000000000000", 2676300),
+        (61254836, "1000000000000", 2676340),
+    ] {
+        let mut other = first.clone();
+        other.global_index = global_index;
+        other.amount = amount.to_string();
+        other.height = height;
+        outs.outputs.push(other);
+    }
+
+    assert_eq!(
+        outs.sorted_by_amount_desc()
+            .iter()
+            .map(|output| output.global_index)
+            .collect::<Vec<_>>(),
+        [61254838, 61254837, 61254839, 61254836]
+    );
+    assert_eq!(
+        outs.sorted_by_age(2676350)
+            .iter()
+            .map(|output| output.global_index)
+            .collect::<Vec<_>>(),
+        [61254837, 61254836, 61254839, 61254838]
+    );
+    // outputs above the current height count as new
+    assert_eq!(
+        outs.sorted_by_age(2676340)
+            .iter()
+            .map(|output| output.global_index)
+            .collect::<Vec<_>>(),
+        [61254837, 61254836, 61254838, 61254839]
+    );
+}
+
+#[test]
+fn test_unspent_outs_select() {
+    let mut outs: UnspentOuts = serde_json::from_str(UNSPENT_OUTS).unwrap();
+    let mut small = outs.outputs[0].clone();
+    small.global_index = 61254840;
+    small.amount = "1000000000000".to_string();
+    outs.outputs.push(small.clone());
+    // too recent to spend
+    small.global_index = 61254841;
+    small.height = 2676349;
+    small.amount = "50000000000000".to_string();
+    outs.outputs.push(small);
+    let height = 2676350;
+
+    let selection = outs
+        .select(10_000_000_000_000, 20000, 10000, height, 16)
+        .unwrap();
+    assert_eq!(selection.inputs.len(), 1);
+    assert_eq!(selection.inputs[0].global_index, 61254839);
+    assert_eq!(selection.total, 15_000_000_000_000);
+    // 1536 bytes for 1 input and 2 outputs
+    assert_eq!(selection.estimated_fee, 30_720_000);
+    assert_eq!(outs.estimate_fee(1, 2, 16), selection.estimated_fee);
+    assert_eq!(
+        selection.change,
+        15_000_000_000_000 - 10_000_000_000_000 - 30_720_000
+    );
+
+    // smaller rings weigh less
+    let small_rings = outs
+        .select(10_000_000_000_000, 20000, 10000, height, 11)
+        .unwrap();
+    assert_eq!(small_rings.estimated_fee, outs.estimate_fee(1, 2, 11));
+    assert!(small_rings.estimated_fee < selection.estimated_fee);
+
+    let selection = outs
+        .select(15_000_000_000_000, 20000, 10000, height, 16)
+        .unwrap();
+    assert_eq!(selection.inputs.len(), 2);
+    assert_eq!(selection.total, 16_000_000_000_000);
+    assert!(selection.estimated_fee > 30_720_000);
+    assert_eq!(selection.estimated_fee % 10000, 0);
+
+    match outs.select(16_000_000_000_000, 20000, 10000, height, 16) {
+        Err(LwsError::InsufficientFunds {
+            required,
+            available,
+        }) => {
+            assert!(required > 16_000_000_000_000);
+            assert_eq!(available, 16_000_000_000_000);
+        }
+        other => panic!("expected insufficient funds, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_camel_case_aliases() {
+    let snake: UnspentOuts = serde_json::from_str(UNSPENT_OUTS).unwrap();
+    let camel: UnspentOuts =
+        serde_json::from_str(include_str!("fixtures/get_unspent_outs_camel_case.json")).unwrap();
+    assert_eq!(camel, snake);
+
+    let mut txs: Value = serde_json::from_str(ADDRESS_TXS).unwrap();
+    let spent = txs["transactions"][1]["spent_outputs"][0].take();
+    txs["transactions"][1]["spent_outputs"][0] = json!({
+        "amount": spent["amount"],
+        "keyImage": spent["key_image"],
+        "txPubKey": spent["tx_pub_key"],
+        "outIndex": spent["out_index"],
+        "mixin": spent["mixin"],
+    });
+    assert_eq!(
+        serde_json::from_value::<AddressTxs>(txs).unwrap(),
+        serde_json::from_str::<AddressTxs>(ADDRESS_TXS).unwrap()
+    );
+}
+
+#[test]
+fn test_mempool_height_zero() {
+    let mut txs: Value = serde_json::from_str(ADDRESS_TXS).unwrap();
+    let mempool = &mut txs["transactions"][2];
+    mempool["height"] = json!(0);
+    mempool["mempool"] = json!(true);
+    let txs: AddressTxs = serde_json::from_value(txs).unwrap();
+    assert!(txs.transactions[2].mempool);
+    assert_eq!(txs.transactions[2].height, None);
+    assert_eq!(txs.transactions[2].confirmations(2676348), None);
+    assert_eq!(
+        txs,
+        serde_json::from_str::<AddressTxs>(ADDRESS_TXS).unwrap()
+    );
+
+    // a mined transaction keeps its height
+    let mut txs: Value = serde_json::from_str(ADDRESS_TXS).unwrap();
+    txs["transactions"][0]["height"] = json!(0);
+    let txs: AddressTxs = serde_json::from_value(txs).unwrap();
+    assert!(!txs.transactions[0].mempool);
+    assert_eq!(txs.transactions[0].height, Some(0));
+}
+
+#[test]
+fn test_verify_totals() {
+    let mut txs: AddressTxs = serde_json::from_str(ADDRESS_TXS).unwrap();
+    // the mempool transaction is not part of the total
+    txs.verify_totals().unwrap();
+
+    txs.transactions.remove(0);
+    match txs.verify_totals() {
+        Err(LwsError::TotalsMismatch { reported, computed }) => {
+            assert_eq!(reported, 35_000_000_000_000);
+            assert_eq!(computed, 15_000_000_000_000);
+        }
+        other => panic!("expected a totals mismatch, got {:?}", other),
+    }
+
+    txs.total_received = "many".to_string();
+    assert!(matches!(
+        txs.verify_totals(),
+        Err(LwsError::InvalidInput { .. })
+    ));
+}
+
+#[test]
+fn test_unspent_outs_validate() {
+    let mut outs: UnspentOuts = serde_json::from_str(UNSPENT_OUTS).unwrap();
+    let mut other = outs.outputs[0].clone();
+    other.index = 1;
+    other.global_index = 61254840;
+    outs.outputs.push(other);
+    outs.validate().unwrap();
+
+    let mut same_global_index = outs.outputs[0].clone();
+    same_global_index.index = 2;
+    let mut same_position = outs.outputs[0].clone();
+    same_position.global_index = 61254841;
+    for duplicate in [same_global_index, same_position] {
+        let mut outs = outs.clone();
+        outs.outputs.push(duplicate.clone());
+        match outs.validate() {
+            Err(LwsError::DuplicateOutput {
+                global_index,
+                tx_hash,
+                index,
+            }) => {
+                assert_eq!(global_index, duplicate.global_index);
+                assert_eq!(tx_hash, duplicate.tx_hash.to_string());
+                assert_eq!(index, duplicate.index);
+            }
+            other => panic!("expected a duplicate output, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_typed_hashes() {
+    let outs: UnspentOuts = serde_json::from_str(UNSPENT_OUTS).unwrap();
+    let output = &outs.outputs[0];
+    let hash: monero::Hash = output.tx_hash.0.into();
+    assert_eq!(TxHash::from(hash), output.tx_hash.0);
+    assert_eq!(output.tx_hash.to_string(), hex::encode(hash.as_bytes()));
+    let public_key = monero::PublicKey::try_from(output.public_key.0).unwrap();
+    assert_eq!(public_key.as_bytes(), output.public_key.0.as_bytes());
+
+    let txs: AddressTxs = serde_json::from_str(ADDRESS_TXS).unwrap();
+    let key_image: &HashString<KeyImage> = &txs.transactions[1].spent_outputs[0].key_image;
+    assert_eq!(
+        key_image.to_string(),
+        "d80a31982ec378985808f6932793f08f7a638424a802888684e2d901ecf7a97e"
+    );
+}
+
+#[test]
+fn test_received_sent_between() {
+    let txs: AddressTxs = serde_json::from_str(ADDRESS_TXS).unwrap();
+    // mined at 2671205 and 2676340, the third one is in the mempool
+    assert_eq!(
+        txs.received_between(0, u64::MAX).unwrap(),
+        35_000_000_000_000
+    );
+    assert_eq!(
+        txs.received_between(2671205, 2676340).unwrap(),
+        35_000_000_000_000
+    );
+    assert_eq!(
+        txs.received_between(2671206, 2676340).unwrap(),
+        15_000_000_000_000
+    );
+    assert_eq!(
+        txs.received_between(2671205, 2676339).unwrap(),
+        20_000_000_000_000
+    );
+    assert_eq!(txs.received_between(2676341, u64::MAX).unwrap(), 0);
+    assert_eq!(
+        txs.sent_between(2676340, 2676340).unwrap(),
+        20_000_000_000_000
+    );
+    assert_eq!(txs.sent_between(0, 2676339).unwrap(), 0);
+}
+
+#[test]
+fn test_confirmations() {
+    let txs: AddressTxs = serde_json::from_str(ADDRESS_TXS).unwrap();
+    let confirmed = &txs.transactions[1];
+    assert_eq!(confirmed.confirmations(2676340), Some(1));
+    assert_eq!(confirmed.confirmations(2676349), Some(10));
+    assert_eq!(confirmed.confirmations(2676339), Some(0));
+    assert!(confirmed.is_confirmed_with(10, 2676349));
+    assert!(!confirmed.is_confirmed_with(11, 2676349));
+
+    let mempool = &txs.transactions[2];
+    assert_eq!(mempool.confirmations(2676349), None);
+    assert!(!mempool.is_confirmed_with(0, 2676349));
+}
+
+#[test]
+fn test_fiat_value() {
+    let mut info: AddressInfo = serde_json::from_str(ADDRESS_INFO).unwrap();
+    assert_eq!(info.fiat_value("AUD"), Some(23.0 * 231.5));
+    assert_eq!(info.fiat_value("aud"), Some(23.0 * 231.5));
+    assert_eq!(info.fiat_value("EUR"), None);
+    info.rates = None;
+    assert_eq!(info.fiat_value("AUD"), None);
+}
+
+#[test]
+fn test_import_payment_uri() {
+    let mut response: ImportResponse = serde_json::from_str(IMPORT_RESPONSE).unwrap();
+    assert_eq!(
+        response.payment_uri().unwrap(),
+        "monero:888tNkZrPN6JsEgekjMnABU4TBzc2Dt29EPAvkRxbANsAnjyPbb3iQ1YBRk1UXcdRsiKc9dhwMVgN5S9cQUiyoogDavup3H\
+         ?tx_amount=1&tx_payment_id=78fa75840f25672d"
+    );
+
+    response.payment_id = None;
+    assert!(!response.payment_uri().unwrap().contains("tx_payment_id"));
+    response.import_fee = None;
+    assert_eq!(response.payment_uri(), None);
+}
+
+#[test]
+fn test_dedup_by_hash() {
+    let mut txs: AddressTxs = serde_json::from_str(ADDRESS_TXS).unwrap();
+    assert_eq!(txs.dedup_by_hash().len(), 3);
+
+    // the mempool transaction shows up again, confirmed
+    let mut confirmed = txs.transactions[2].clone();
+    confirmed.mempool = false;
+    confirmed.height = Some(2_700_000);
+    txs.transactions.push(confirmed.clone());
+    txs.transactions.push(txs.transactions[0].clone());
+
+    let deduped = txs.dedup_by_hash();
+    assert_eq!(deduped.len(), 3);
+    assert_eq!(deduped[0], &txs.transactions[0]);
+    assert_eq!(deduped[2], &confirmed);
+}
+
+#[test]
+fn test_balance_summary() {
+    let mut info: AddressInfo = serde_json::from_str(ADDRESS_INFO).unwrap();
+    info.locked_funds = "3000000000000".to_string();
+    assert_eq!(
+        BalanceSummary::try_from(&info).unwrap(),
+        BalanceSummary {
+            total_received: 35_000_000_000_000,
+            total_sent: 12_000_000_000_000,
+            locked: 3_000_000_000_000,
+            unlocked: 20_000_000_000_000,
+            net: 23_000_000_000_000,
+        }
+    );
+
+    info.total_sent = "36000000000000".to_string();
+    let summary = BalanceSummary::try_from(&info).unwrap();
+    assert_eq!((summary.unlocked, summary.net), (0, -1_000_000_000_000));
+
+    info.locked_funds = "1.5".to_string();
+    assert!(matches!(
+        BalanceSummary::try_from(&info),
+        Err(LwsError::InvalidInput { input, .. }) if input == "1.5"
+    ));
+}
+
+#[test]
+fn test_random_outs_by_amount() {
+    let (outs, _) = round_trip::<AmountOuts>(RANDOM_OUTS_MULTI);
+    let by_amount = outs.by_amount();
+    assert_eq!(by_amount.len(), 3);
+    assert_eq!(by_amount[&0].outputs.len(), 3);
+    assert_eq!(by_amount[&20_000_000_000].outputs[0].global_index, 1000);
+    assert_eq!(by_amount[&100_000_000_000].amount, "100000000000");
+    assert!(!by_amount.contains_key(&1));
+}
+
+#[test]
+fn test_is_fully_scanned() {
+    let mut info: AddressInfo = serde_json::from_str(ADDRESS_INFO).unwrap();
+    assert!(info.is_fully_scanned());
+    info.scanned_block_height -= 1;
+    assert!(info.is_fully_scanned());
+    info.scanned_block_height -= 1;
+    assert!(!info.is_fully_scanned());
+}
+
+#[test]
+fn test_output_spend_height() {
+    let outs: UnspentOuts = serde_json::from_str(UNSPENT_OUTS).unwrap();
+    assert_eq!(outs.outputs[0].spend_height, None);
+
+    let mut body: Value = serde_json::from_str(UNSPENT_OUTS).unwrap();
+    body["outputs"][0]["spend_height"] = json!(2676400);
+    let outs: UnspentOuts = serde_json::from_value(body.clone()).unwrap();
+    assert_eq!(outs.outputs[0].spend_height, Some(2676400));
+    assert_eq!(serde_json::to_value(&outs).unwrap(), body);
+}
+
+#[test]
+fn test_parse() {
+    let txs = parse::parse_address_txs(ADDRESS_TXS.as_bytes()).unwrap();
+    assert_eq!(
+        txs,
+        serde_json::from_str::<AddressTxs>(ADDRESS_TXS).unwrap()
+    );
+    assert_eq!(
+        parse::parse_login_response(LOGIN_RESPONSE.as_bytes())
+            .unwrap()
+            .start_height,
+        Some(2670000)
+    );
+
+    match parse::parse_address_info(ERROR_BAD_VIEW_KEY.as_bytes()) {
+        Err(LwsError::Server { status, reason }) => {
+            assert_eq!(status, "error");
+            assert_eq!(reason.as_deref(), Some("Viewkey provided is incorrect"));
+        }
+        other => panic!("expected a server error, got {:?}", other),
+    }
+    assert!(matches!(
+        parse::parse_unspent_outs(ADDRESS_TXS.as_bytes()),
+        Err(LwsError::Deserialize { .. })
+    ));
+    assert!(matches!(
+        parse::parse_address_txs(b"<html>"),
+        Err(LwsError::Deserialize { .. })
+    ));
+}
+
+#[cfg(feature = "zerocopy")]
+#[test]
+fn test_borrowed_address_txs() {
+    use monero_lws::borrowed::AddressTxsRef;
+    use std::borrow::Cow;
+
+    let txs: AddressTxsRef = serde_json::from_str(ADDRESS_TXS).unwrap();
+    assert!(matches!(txs.transactions[0].timestamp, Cow::Borrowed(_)));
+    assert!(matches!(txs.total_received, Cow::Borrowed(_)));
+    assert_eq!(
+        txs.into_owned(),
+        serde_json::from_str::<AddressTxs>(ADDRESS_TXS).unwrap()
+    );
+
+    let mut body: Value = serde_json::from_str(ADDRESS_TXS).unwrap();
+    body["transactions"][0]["height"] = json!(0);
+    body["transactions"][2]["height"] = json!(0);
+    let body = body.to_string();
+    let txs: AddressTxsRef = serde_json::from_str(&body).unwrap();
+    assert_eq!(txs.transactions[0].height, Some(0));
+    assert_eq!(txs.transactions[2].height, None);
+}
+
+#[test]
+fn test_spendable_outputs() {
+    let outs: UnspentOuts = serde_json::from_str(UNSPENT_OUTS).unwrap();
+    let output = &outs.outputs[0];
+    assert!(!output.spendable_at(output.height - 1));
+    assert!(!output.spendable_at(output.height + 8));
+    assert!(output.spendable_at(output.height + 9));
+    assert!(outs.spendable_now(output.height).is_empty());
+    assert_eq!(outs.spendable_now(output.height + 9), vec![output]);
+}
+
+/// Deserializes `fixture` with the collection at `path` removed and emptied, both must give an
+/// empty collection.
+fn assert_missing_or_empty<T>(fixture: &str, path: &[&str], len: impl Fn(&T) -> usize)
+where
+    T: DeserializeOwned,
+{
+    let golden: Value = serde_json::from_str(fixture).unwrap();
+    let (key, parents) = path.split_last().unwrap();
+    for missing in [true, false] {
+        let mut body = golden.clone();
+        let parent = parents
+            .iter()
+            .fold(&mut body, |value, segment| match segment.parse::<usize>() {
+                Ok(index) => &mut value[index],
+                Err(_) => &mut value[*segment],
+            })
+            .as_object_mut()
+            .unwrap();
+        if missing {
+            parent.remove(*key);
+        } else {
+            parent.insert(key.to_string(), json!([]));
+        }
+        let model: T = serde_json::from_value(body).unwrap();
+        assert_eq!(len(&model), 0, "{:?} missing: {}", path, missing);
+    }
+}
+
+#[test]
+fn test_missing_or_empty_collections() {
+    assert_missing_or_empty(ADDRESS_TXS, &["transactions"], |txs: &AddressTxs| {
+        txs.transactions.len()
+    });
+    assert_missing_or_empty(
+        ADDRESS_TXS,
+        &["transactions", "0", "spent_outputs"],
+        |txs: &AddressTxs| txs.transactions[0].spent_outputs.len(),
+    );
+    assert_missing_or_empty(ADDRESS_INFO, &["spent_outputs"], |info: &AddressInfo| {
+        info.spent_outputs.len()
+    });
+    assert_missing_or_empty(UNSPENT_OUTS, &["outputs"], |outs: &UnspentOuts| {
+        outs.outputs.len()
+    });
+    assert_missing_or_empty(
+        UNSPENT_OUTS,
+        &["outputs", "0", "spend_key_images"],
+        |outs: &UnspentOuts| outs.outputs[0].spend_key_images.len(),
+    );
+    assert_missing_or_empty(RANDOM_OUTS, &["amount_outs"], |outs: &AmountOuts| {
+        outs.amount_outs.len()
+    });
+}
+
+#[test]
+fn test_running_balance() {
+    let mut txs: AddressTxs = serde_json::from_str(ADDRESS_TXS).unwrap();
+    txs.transactions.reverse();
+    let running: Vec<_> = txs
+        .with_running_balance()
+        .into_iter()
+        .map(|(tx, balance)| (tx.height, tx.mempool, balance))
+        .collect();
+    assert_eq!(
+        running,
+        [
+            (Some(2671205), false, 20_000_000_000_000),
+            (Some(2676340), false, 15_000_000_000_000),
+            (None, true, 17_000_000_000_000),
+        ]
+    );
+}
+
+#[test]
+fn test_tx_direction() {
+    let mut txs: AddressTxs = serde_json::from_str(ADDRESS_TXS).unwrap();
+    assert_eq!(txs.transactions[0].direction(), TxDirection::Incoming);
+    assert_eq!(txs.transactions[1].direction(), TxDirection::Internal);
+    txs.transactions[1].total_received = "0".to_string();
+    assert_eq!(txs.transactions[1].direction(), TxDirection::Outgoing);
+}
+
+#[test]
+fn test_tx_net_amount() {
+    let mut txs: AddressTxs = serde_json::from_str(ADDRESS_TXS).unwrap();
+    assert_eq!(txs.transactions[0].net_amount(), Ok(20000000000000));
+    // internal transaction, spending 20 XMR with 15 XMR of change
+    assert_eq!(txs.transactions[1].direction(), TxDirection::Internal);
+    assert_eq!(txs.transactions[1].net_amount(), Ok(-5000000000000));
+    txs.transactions[1].total_received = "0".to_string();
+    assert_eq!(txs.transactions[1].net_amount(), Ok(-20000000000000));
+    txs.transactions[1].total_received = "1.5".to_string();
+    assert!(txs.transactions[1].net_amount().is_err());
+}
+
+#[test]
+fn test_most_synced() {
+    let info: AddressInfo = serde_json::from_str(ADDRESS_INFO).unwrap();
+    let mut behind = info.clone();
+    behind.scanned_block_height -= 10;
+    let mut longer_chain = info.clone();
+    longer_chain.blockchain_height += 1;
+
+    assert!(info.is_more_synced_than(&behind));
+    assert!(!behind.is_more_synced_than(&info));
+    assert!(!info.is_more_synced_than(&info));
+    assert!(longer_chain.is_more_synced_than(&info));
+
+    let infos = [behind, info.clone(), longer_chain.clone(), info];
+    assert_eq!(AddressInfo::most_synced(&infos), Some(&longer_chain));
+    assert_eq!(AddressInfo::most_synced(&infos[..2]), Some(&infos[1]));
+    assert_eq!(AddressInfo::most_synced(&[]), None);
+}
+
+#[test]
+fn test_build_ring() {
+    let outs: AmountOuts = serde_json::from_str(RANDOM_OUTS).unwrap();
+    let decoys = &outs.amount_outs[0];
+    let mut real = decoys.outputs[3].clone();
+
+    // the decoy colliding with the real output is skipped
+    let ring = decoys.build_ring(real.clone(), 16).unwrap();
+    assert_eq!(ring.len(), 16);
+    assert!(ring
+        .windows(2)
+        .all(|w| w[0].global_index < w[1].global_index));
+    assert_eq!(ring.iter().filter(|output| **output == real).count(), 1);
+    assert!(matches!(
+        decoys.build_ring(real.clone(), 17),
+        Err(LwsError::NotEnoughDecoys {
+            required: 16,
+            available: 15
+        })
+    ));
+
+    for ring_size in [0, 1] {
+        assert!(matches!(
+            decoys.build_ring(real.clone(), ring_size),
+            Err(LwsError::InvalidInput { .. })
+        ));
+    }
+
+    real.global_index = 1;
+    let ring = decoys.build_ring(real.clone(), 16).unwrap();
+    assert_eq!(ring[0], real);
+    assert!(!ring.contains(&decoys.outputs[15]));
+}
+
+#[test]
+fn test_spendable_unlock_time() {
+    let outs: UnspentOuts = serde_json::from_str(UNSPENT_OUTS).unwrap();
+    let mut output = outs.outputs[0].clone();
+    let mature = output.height + 9;
+
+    output.unlock_time = Some(0);
+    assert!(output.spendable_at(mature));
+    output.unlock_time = Some(mature + 10);
+    assert!(!output.spendable_at(mature));
+    assert!(output.spendable_at(mature + 9));
+
+    // timestamps: long past, and far in the future
+    output.unlock_time = Some(1_500_000_000);
+    assert!(output.spendable_at(mature));
+    output.unlock_time = Some(u64::MAX / 2);
+    assert!(!output.spendable_at(mature));
+}
+
+#[test]
+fn test_spendable_coinbase() {
+    let mut outs: Value = serde_json::from_str(UNSPENT_OUTS).unwrap();
+    let outs_parsed: UnspentOuts = serde_json::from_value(outs.clone()).unwrap();
+    assert!(!outs_parsed.outputs[0].coinbase);
+
+    outs["outputs"][0]["coinbase"] = json!(true);
+    let outs: UnspentOuts = serde_json::from_value(outs).unwrap();
+    let output = &outs.outputs[0];
+    assert!(output.coinbase);
+    assert!(!output.spendable_at(output.height + 9));
+    assert!(!output.spendable_at(output.height + 58));
+    assert!(output.spendable_at(output.height + 59));
+    assert_eq!(
+        serde_json::to_value(output).unwrap()["coinbase"],
+        json!(true)
+    );
+    assert!(serde_json::to_value(&outs_parsed.outputs[0]).unwrap()["coinbase"].is_null());
+}
+
+#[test]
+fn test_deserialize_error_path() {
+    let mut body: Value = serde_json::from_str(ADDRESS_INFO).unwrap();
+    body["spent_outputs"][0]["out_index"] = json!("first");
+    let err = parse::parse_address_info(body.to_string().as_bytes()).unwrap_err();
+    let path = match &err {
+        LwsError::Deserialize { path, .. } => path.as_deref(),
+        other => panic!("expected a deserialization error, got {:?}", other),
+    };
+    if cfg!(feature = "error-paths") {
+        assert_eq!(path, Some("spent_outputs[0].out_index"));
+        assert_eq!(
+            err.to_string(),
+            "invalid response at `spent_outputs[0].out_index`"
+        );
+    } else {
+        assert_eq!(path, None);
+    }
+}
+
+#[test]
+fn test_merge_address_txs() {
+    let full: AddressTxs = serde_json::from_str(ADDRESS_TXS).unwrap();
+    let mut merged = full.clone();
+    merged.transactions.truncate(1);
+
+    // the newer poll sees the mempool transaction mined, and the first one again from the mempool
+    let mut newer = full.clone();
+    newer.scanned_block_height += 5;
+    newer.transactions[2].mempool = false;
+    newer.transactions[2].height = Some(2676350);
+    newer.transactions[0].mempool = true;
+    newer.transactions.swap(0, 2);
+    merged.merge(newer.clone());
+
+    assert_eq!(merged.scanned_block_height, full.scanned_block_height + 5);
+    assert_eq!(merged.transactions.len(), 3);
+    assert_eq!(merged.transactions[0], full.transactions[0]);
+    assert_eq!(merged.transactions[1], newer.transactions[0]);
+    assert_eq!(merged.transactions[2], newer.transactions[1]);
+}
+
+/// Re-serializing a captured response, status included, must give back the same JSON, e.g. for
+/// a proxy relaying responses.
+fn assert_wire_round_trip<T>(body: Value)
+where
+    T: Serialize + DeserializeOwned + Debug,
+{
+    let result: MoneroResult<T> = serde_json::from_value(body.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&result).unwrap(), body);
+}
+
+fn with_ok_status(fixture: &str) -> Value {
+    let mut body: Value = serde_json::from_str(fixture).unwrap();
+    body["status"] = json!("OK");
+    body
+}
+
+#[test]
+fn test_wire_round_trip() {
+    assert_wire_round_trip::<AddressInfo>(with_ok_status(ADDRESS_INFO));
+    assert_wire_round_trip::<AddressTxs>(with_ok_status(ADDRESS_TXS));
+    assert_wire_round_trip::<UnspentOuts>(with_ok_status(UNSPENT_OUTS));
+    assert_wire_round_trip::<AmountOuts>(with_ok_status(RANDOM_OUTS));
+    assert_wire_round_trip::<LoginResponse>(with_ok_status(LOGIN_RESPONSE));
+
+    // optional fields servers omit are not re-emitted as null
+    let mut login = with_ok_status(LOGIN_RESPONSE);
+    login.as_object_mut().unwrap().remove("start_height");
+    assert_wire_round_trip::<LoginResponse>(login);
+    let mut info = with_ok_status(ADDRESS_INFO);
+    info.as_object_mut().unwrap().remove("rates");
+    assert_wire_round_trip::<AddressInfo>(info);
+    let mut import: Value = serde_json::from_str(IMPORT_RESPONSE).unwrap();
+    for key in ["payment_address", "payment_id", "import_fee"] {
+        import.as_object_mut().unwrap().remove(key);
+    }
+    let response: ImportResponse = serde_json::from_value(import.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&response).unwrap(), import);
+
+    // failures are re-emitted in their canonical shape
+    assert_wire_round_trip::<AddressInfo>(
+        json!({ "status": "Failed", "reason": "Address not found" }),
+    );
+    assert_wire_round_trip::<AddressInfo>(json!({ "status": "Failed" }));
+}
+
+struct FixedClock(u64);
+
+impl Clock for FixedClock {
+    fn now(&self) -> u64 {
+        self.0
+    }
+}
+
+#[test]
+fn test_spendable_with_clock() {
+    let outs: UnspentOuts = serde_json::from_str(UNSPENT_OUTS).unwrap();
+    let mut output = outs.outputs[0].clone();
+    let mature = output.height + 9;
+    output.unlock_time = Some(1_700_000_000);
+
+    assert!(!output.spendable_with(mature, &FixedClock(1_699_999_000)));
+    // the daemon accepts spending up to two minutes early
+    assert!(output.spendable_with(mature, &FixedClock(1_699_999_880)));
+    assert!(output.spendable_with(mature, &FixedClock(1_700_000_000)));
+    assert!(!output.spendable_with(mature - 1, &FixedClock(1_700_000_000)));
+}
+
+#[test]
+fn test_select_decoys_gamma() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let outs: AmountOuts = serde_json::from_str(RANDOM_OUTS).unwrap();
+    let template = &outs.amount_outs[0].outputs[0];
+    // one output every 100000 over the chain, the newest at 100000000
+    let pool: Vec<_> = (1..=1000u64)
+        .map(|i| {
+            let mut output = template.clone();
+            output.global_index = i * 100_000;
+            output
+        })
+        .collect();
+    let mut rng = StdRng::seed_from_u64(7);
+
+    let decoys = select_decoys_gamma(&pool, 15, &mut rng);
+    assert_eq!(decoys.len(), 15);
+    assert!(decoys
+        .windows(2)
+        .all(|w| w[0].global_index < w[1].global_index));
+    let recent = decoys
+        .iter()
+        .filter(|output| output.global_index > 90_000_000)
+        .count();
+    assert!(recent > 10, "{} recent decoys", recent);
+
+    assert_eq!(select_decoys_gamma(&pool[..5], 15, &mut rng).len(), 5);
+    assert!(select_decoys_gamma(&[], 15, &mut rng).is_empty());
+}
+
+#[cfg(feature = "ringct")]
+#[test]
+fn test_ring_member() {
+    use monero_lws::{RandomOutput, RingMember};
+    use std::convert::TryFrom;
+
+    // an output of a mainnet transaction, as the server would report it as a decoy
+    let raw_tx = hex::decode(include_str!("fixtures/raw_tx.hex").trim()).unwrap();
+    let tx: monero::Transaction = monero::consensus::encode::deserialize(&raw_tx).unwrap();
+    let key = tx.prefix().outputs[0].get_one_time_key().unwrap();
+    let commitment = tx.rct_signatures.sig.as_ref().unwrap().out_pk[0].mask;
+    let decoy: RandomOutput = serde_json::from_value(json!({
+        "global_index": 61001000,
+        "public_key": hex::encode(key.as_bytes()),
+        "rct": hex::encode(commitment.key),
+    }))
+    .unwrap();
+    let member = RingMember::try_from(&decoy).unwrap();
+    assert_eq!(member.global_index, 61001000);
+    assert_eq!(member.dest, key);
+    assert_eq!(member.commitment.mask, commitment);
+    assert_eq!(RingMember::try_from(decoy.clone()).unwrap(), member);
+
+    // not a point of the curve
+    let mut invalid = decoy;
+    invalid.rct = HashString(monero::Hash([0xff; 32]));
+    match RingMember::try_from(&invalid) {
+        Err(LwsError::InvalidInput { input, .. }) => assert_eq!(input, "ff".repeat(32)),
+        other => panic!("expected invalid input, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_output_clear_amount() {
+    let outs: UnspentOuts = serde_json::from_str(UNSPENT_OUTS).unwrap();
+    let mut output = outs.outputs[0].clone();
+    assert!(output.is_rct());
+    assert_eq!(output.clear_amount(), None);
+    output.amount = "0".to_string();
+    assert_eq!(output.clear_amount(), None);
+
+    output.rct = Vec::new();
+    assert!(!output.is_rct());
+    assert_eq!(output.clear_amount(), None);
+    output.amount = "15000000000000".to_string();
+    assert_eq!(output.clear_amount(), Some(15_000_000_000_000));
+}
+
+#[test]
+fn test_to_json_pretty() {
+    let info: AddressInfo = serde_json::from_str(ADDRESS_INFO).unwrap();
+    let pretty = info.to_json_pretty().unwrap();
+    assert!(pretty.contains("\n  \"locked_funds\": "));
+    assert_eq!(serde_json::from_str::<AddressInfo>(&pretty).unwrap(), info);
+}
+
+#[cfg(feature = "verify")]
+#[test]
+fn test_verify_prefix_hash() {
+    use monero::cryptonote::hash::Hashable;
+
+    let raw_tx = hex::decode(include_str!("fixtures/raw_tx.hex").trim()).unwrap();
+    let tx: monero::Transaction = monero::consensus::encode::deserialize(&raw_tx).unwrap();
+    let outs: UnspentOuts = serde_json::from_str(UNSPENT_OUTS).unwrap();
+    let mut output = outs.outputs[0].clone();
+    assert!(!output.verify_prefix_hash(&tx));
+
+    output.tx_prefix_hash = tx.prefix().hash().as_bytes().to_vec();
+    assert!(output.verify_prefix_hash(&tx));
+    output.tx_prefix_hash.pop();
+    assert!(!output.verify_prefix_hash(&tx));
+}
+
+#[cfg(feature = "decimal")]
+#[test]
+fn test_decimal_amounts() {
+    let info: AddressInfo = serde_json::from_str(ADDRESS_INFO).unwrap();
+    let balance = BalanceSummary::try_from(&info).unwrap();
+    assert_eq!(balance.total_received_xmr().to_string(), "35.000000000000");
+    assert_eq!(
+        balance.net_xmr(),
+        balance.total_received_xmr() - balance.total_sent_xmr()
+    );
+
+    let mut txs: AddressTxs = serde_json::from_str(ADDRESS_TXS).unwrap();
+    let tx = &mut txs.transactions[1];
+    assert_eq!(tx.total_sent_xmr().unwrap().to_string(), "20.000000000000");
+    tx.total_received = "1".to_string();
+    assert_eq!(
+        tx.total_received_xmr().unwrap().to_string(),
+        "0.000000000001"
+    );
+    tx.total_received = "-1".to_string();
+    assert!(tx.total_received_xmr().is_err());
+
+    let outs: UnspentOuts = serde_json::from_str(UNSPENT_OUTS).unwrap();
+    assert_eq!(
+        outs.outputs[0].amount_xmr().unwrap().to_string(),
+        "15.000000000000"
+    );
+}
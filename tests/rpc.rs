@@ -46,6 +46,10 @@ async fn functional_lws_daemon_test() {
         .await
         .unwrap();
 
+    let account = monero_lws_client.account(address, view_key);
+    account.info().await.unwrap();
+    account.txs().await.unwrap();
+
     monero_lws_client
         .get_random_outs(11, vec![monero::Amount::from_pico(1000000)])
         .await
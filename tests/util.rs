@@ -0,0 +1,181 @@
+use std::str::FromStr;
+
+use monero_lws::{
+    detect_network, estimate_tx_weight, from_relative_offsets, make_integrated_address,
+    parse_address, payment_uri, to_relative_offsets, LwsError, PaymentIdField,
+};
+
+#[test]
+fn test_parse_address() {
+    let address = "888tNkZrPN6JsEgekjMnABU4TBzc2Dt29EPAvkRxbANsAnjyPbb3iQ1YBRk1UXcdRsiKc9dhwMVgN5S9cQUiyoogDavup3H";
+    assert_eq!(parse_address(address).unwrap().to_string(), address);
+
+    match parse_address("not-an-address") {
+        Err(LwsError::InvalidInput { input, .. }) => assert_eq!(input, "not-an-address"),
+        other => panic!("expected invalid input, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_make_integrated_address() {
+    let key = monero::PrivateKey::from_str(
+        "c2c5bf0e6c1a43f5e0c4a4b7ba7c1b8a3c8e7ab5a3e2f8a4c5d6e7f8091a2b0c",
+    )
+    .unwrap();
+    let public_key = monero::PublicKey::from_private_key(&key);
+    let address = monero::Address::standard(monero::Network::Mainnet, public_key, public_key);
+    let payment_id = "78fa75840f25672d".parse().unwrap();
+    let integrated = make_integrated_address(&address, &payment_id).unwrap();
+    assert_eq!(
+        integrated.addr_type,
+        monero::AddressType::Integrated(payment_id)
+    );
+    assert_eq!(integrated.public_spend, address.public_spend);
+    assert_eq!(parse_address(&integrated.to_string()).unwrap(), integrated);
+
+    for address in [
+        integrated,
+        parse_address("888tNkZrPN6JsEgekjMnABU4TBzc2Dt29EPAvkRxbANsAnjyPbb3iQ1YBRk1UXcdRsiKc9dhwMVgN5S9cQUiyoogDavup3H").unwrap(),
+    ] {
+        match make_integrated_address(&address, &payment_id) {
+            Err(LwsError::InvalidInput { input, .. }) => assert_eq!(input, address.to_string()),
+            other => panic!("expected invalid input, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_detect_network() {
+    let key = monero::PrivateKey::from_str(
+        "c2c5bf0e6c1a43f5e0c4a4b7ba7c1b8a3c8e7ab5a3e2f8a4c5d6e7f8091a2b0c",
+    )
+    .unwrap();
+    let public_key = monero::PublicKey::from_private_key(&key);
+    for network in [
+        monero::Network::Mainnet,
+        monero::Network::Testnet,
+        monero::Network::Stagenet,
+    ] {
+        let address = monero::Address::standard(network, public_key, public_key);
+        let address = parse_address(&address.to_string()).unwrap();
+        assert_eq!(detect_network(&address), network);
+    }
+}
+
+#[test]
+fn test_estimate_tx_weight() {
+    assert_eq!(estimate_tx_weight(1, 2, 16), 1536);
+    assert_eq!(estimate_tx_weight(2, 2, 16), 2215);
+    // the third output pads the range proof to 4 outputs, which weighs more than its size
+    assert_eq!(estimate_tx_weight(1, 3, 16), 2139);
+}
+
+#[cfg(feature = "decimal")]
+#[test]
+fn test_piconero_to_xmr() {
+    use monero_lws::piconero_to_xmr;
+
+    assert_eq!(piconero_to_xmr(0).to_string(), "0.000000000000");
+    assert_eq!(piconero_to_xmr(1).to_string(), "0.000000000001");
+    assert_eq!(
+        piconero_to_xmr(1_000_000_000_000).to_string(),
+        "1.000000000000"
+    );
+    assert_eq!(
+        piconero_to_xmr(u64::MAX).to_string(),
+        "18446744.073709551615"
+    );
+}
+
+#[test]
+fn test_payment_id_field() {
+    let short = PaymentIdField::from_hex("78fa75840f25672d").unwrap();
+    assert!(short.is_short());
+    assert_eq!(short.len(), 8);
+    assert_eq!(
+        short,
+        PaymentIdField::Short("78fa75840f25672d".parse().unwrap())
+    );
+    assert_eq!(short.to_string(), "78fa75840f25672d");
+
+    let hex = "15ec997f8ce06b209be790c61bacf0f223faca1be2c6bb3d4c36af4d676f0234";
+    let long: PaymentIdField = hex.parse().unwrap();
+    assert!(!long.is_short());
+    assert_eq!(long.len(), 32);
+    assert_eq!(long.to_string(), hex);
+
+    for malformed in [
+        "78fa75840f2567",
+        "78fa75840f25672d00",
+        "78fa75840f25672z",
+        "",
+    ] {
+        match PaymentIdField::from_hex(malformed) {
+            Err(LwsError::InvalidInput { input, .. }) => assert_eq!(input, malformed),
+            other => panic!("expected invalid input, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_payment_uri() {
+    let address = parse_address("888tNkZrPN6JsEgekjMnABU4TBzc2Dt29EPAvkRxbANsAnjyPbb3iQ1YBRk1UXcdRsiKc9dhwMVgN5S9cQUiyoogDavup3H").unwrap();
+    let base = format!("monero:{}", address);
+    assert_eq!(payment_uri(&address, None, None, None), base);
+
+    for (amount, xmr) in [
+        (1_500_000_000_000, "1.5"),
+        (2_000_000_000_000, "2"),
+        (1, "0.000000000001"),
+        (0, "0"),
+    ] {
+        assert_eq!(
+            payment_uri(&address, Some(amount), None, None),
+            format!("{}?tx_amount={}", base, xmr)
+        );
+    }
+
+    let payment_id = PaymentIdField::from_hex("78fa75840f25672d").unwrap();
+    assert_eq!(
+        payment_uri(&address, None, Some(&payment_id), None),
+        format!("{}?tx_payment_id=78fa75840f25672d", base)
+    );
+
+    assert_eq!(
+        payment_uri(&address, None, None, Some("Coffee & cake, 100%?")),
+        format!("{}?tx_description=Coffee%20%26%20cake%2C%20100%25%3F", base)
+    );
+    assert_eq!(
+        payment_uri(&address, None, None, Some("caf\u{e9}=ok")),
+        format!("{}?tx_description=caf%C3%A9%3Dok", base)
+    );
+
+    assert_eq!(
+        payment_uri(
+            &address,
+            Some(250_000_000_000),
+            Some(&payment_id),
+            Some("Invoice #42")
+        ),
+        format!(
+            "{}?tx_amount=0.25&tx_payment_id=78fa75840f25672d&tx_description=Invoice%20%2342",
+            base
+        )
+    );
+}
+
+#[test]
+fn test_relative_offsets() {
+    let indices = [61254839, 2500000, 48000123, 61254701, 59890034];
+    let offsets = to_relative_offsets(&indices);
+    assert_eq!(offsets, [2500000, 45500123, 11889911, 1364667, 138]);
+    assert_eq!(
+        from_relative_offsets(&offsets),
+        [2500000, 48000123, 59890034, 61254701, 61254839]
+    );
+
+    assert_eq!(to_relative_offsets(&[7]), [7]);
+    assert_eq!(to_relative_offsets(&[0, 5, 5]), [0, 5, 0]);
+    assert!(to_relative_offsets(&[]).is_empty());
+    assert!(from_relative_offsets(&[]).is_empty());
+}